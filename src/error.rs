@@ -19,6 +19,31 @@ pub enum Error {
     AuthorizationNotConfigured,
     #[error("Invalid URL: {0}")]
     URLParse(#[from] url::ParseError),
+    #[error(transparent)]
+    HashMismatch(#[from] HashMismatch),
+    #[error("{0} is not cached and requests are not allowed")]
+    NotCached(String),
+    #[error("Version {0} is not in the version manifest")]
+    UnknownVersion(String),
+    #[error("Asset {0} is not in the asset index")]
+    UnknownAsset(String),
+}
+impl Error {
+    /// If the error was caused by not being able to reach the server
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            Error::ReqwestError(err) => err.is_connect() || err.is_timeout(),
+            _ => false,
+        }
+    }
+}
+/// The downloaded or stored data did not match the expected hash
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Hash mismatch for {location}. Expected {expected} got {actual}")]
+pub struct HashMismatch {
+    pub location: String,
+    pub expected: String,
+    pub actual: String,
 }
 impl ResponseError for Error {
     fn status_code(&self) -> reqwest::StatusCode {
@@ -1,14 +1,28 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
+use reqwest::header::ACCEPT;
 use serde::{Deserialize, Serialize};
-use tokio::fs::create_dir_all;
+use tokio::{fs::create_dir_all, task::JoinSet};
 
 use crate::{
-    game_files::assets::{content_hash, file_path, Asset},
-    utils::download::{Download, DownloadToFile},
+    game_files::{
+        assets::{content_hash, store::object_path, Asset},
+        release::data::AssetIndex,
+        Endpoint,
+    },
+    utils::{
+        download::{Download, DownloadToFile},
+        hash::{check_file, verify_sha1, FileState},
+    },
     APIClient, Error,
 };
 
+/// The number of objects checked at once by [AssetFile::plan]
+const CHECK_CONCURRENCY: usize = 64;
+
 /// The Asset File
 /// Asset File Example 1.19 ['{launcher_meta}/v1/packages/c76d769e6bf9c90a7ffff1481a05563777356749/1.19.json'](https://launchermeta.mojang.com/v1/packages/c76d769e6bf9c90a7ffff1481a05563777356749/1.19.json)
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -16,6 +30,13 @@ pub struct AssetFile {
     /// Will be found true on legacy versions.
     #[serde(default)]
     pub map_to_resources: bool,
+    /// Found on the `legacy` index. The assets are read from `virtual/{index_id}`
+    #[serde(
+        default,
+        rename = "virtual",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub is_virtual: bool,
     pub objects: HashMap<String, AssetResponse>,
 }
 
@@ -26,6 +47,28 @@ pub struct AssetResponse {
     pub size: u32,
 }
 
+impl AssetIndex {
+    /// Pulls the [AssetFile] the index points to. Verified against [AssetIndex::sha1]
+    pub async fn get_asset_file(&self, client: &APIClient) -> Result<AssetFile, Error> {
+        let bytes = self.get_asset_file_bytes(client).await?;
+        serde_json::from_slice(&bytes).map_err(Error::from)
+    }
+    /// Pulls the [AssetFile] without parsing it. Verified against [AssetIndex::sha1]
+    pub async fn get_asset_file_bytes(&self, client: &APIClient) -> Result<Vec<u8>, Error> {
+        let url = client.game_files.redirect(&self.url)?;
+        let bytes = client
+            .process_bytes(
+                client
+                    .http_client
+                    .get(url)
+                    .header(ACCEPT, "application/json"),
+            )
+            .await?;
+        verify_sha1(&self.url, &bytes, &self.sha1)?;
+        Ok(bytes)
+    }
+}
+
 impl AssetFile {
     /// Get an Asset from the the AssetFile
     pub fn get_asset<'a, S: AsRef<str>>(&self, name: S) -> Option<Asset> {
@@ -34,50 +77,180 @@ impl AssetFile {
             name: name.as_ref().to_string(),
         })
     }
-    /// Downloads a file. If the file already exists. it will be overwritten
-    /// `asset_dir` is the directory to download assets to
-    /// Returns a HashMap<String, [DownloadFile](crate::utils::download::DownloadFile<'a>)> Key is the hash of the file
-    pub async fn download(
-        self,
-        client: APIClient,
-        asset_dir: PathBuf,
-    ) -> Result<HashMap<String, DownloadToFile>, Error> {
-        let mut downloads = HashMap::new();
-        if !asset_dir.exists() {
-            create_dir_all(&asset_dir).await?;
+    /// Plans the download of the objects missing from `asset_dir`. Existing objects are only checked for existence
+    ///
+    /// Assets are always placed in `objects/` by hash. Use [AssetStore::materialize](crate::game_files::assets::store::AssetStore::materialize) for versions reading assets by name
+    pub async fn download(self, client: APIClient, asset_dir: PathBuf) -> Result<AssetPlan, Error> {
+        self.plan(&client, &asset_dir, AssetCheck::Exists).await
+    }
+    /// Checks the objects in `asset_dir` concurrently and plans the download of the missing and corrupt ones.
+    ///
+    /// Objects shared by multiple names are only checked and downloaded once
+    pub async fn plan(
+        &self,
+        client: &APIClient,
+        asset_dir: &Path,
+        check: AssetCheck,
+    ) -> Result<AssetPlan, Error> {
+        create_dir_all(asset_dir).await?;
+        let objects: HashMap<&str, u32> = self
+            .objects
+            .values()
+            .map(|object| (object.hash.as_str(), object.size))
+            .collect();
+        let mut plan = AssetPlan::default();
+        let mut checks = JoinSet::new();
+        for (hash, size) in objects {
+            if checks.len() >= CHECK_CONCURRENCY {
+                if let Some(checked) = checks.join_next().await {
+                    plan.add(
+                        client,
+                        checked.map_err(|err| Error::Custom(err.to_string()))??,
+                    )?;
+                }
+            }
+            let hash = hash.to_owned();
+            let location = object_path(asset_dir, &hash);
+            checks.spawn(async move {
+                let state = match check {
+                    AssetCheck::Exists => check_file(&location, None, None).await?,
+                    AssetCheck::Size => check_file(&location, Some(size as u64), None).await?,
+                    AssetCheck::Hash => {
+                        check_file(&location, Some(size as u64), Some(&hash)).await?
+                    }
+                };
+                Ok::<_, Error>((hash, size, location, state))
+            });
+        }
+        while let Some(checked) = checks.join_next().await {
+            plan.add(
+                client,
+                checked.map_err(|err| Error::Custom(err.to_string()))??,
+            )?;
         }
+        Ok(plan)
+    }
+}
 
-        for (name, response) in self.objects {
-            if downloads.contains_key(&response.hash) {
-                continue;
+/// How thoroughly existing objects are checked by [AssetFile::plan]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AssetCheck {
+    #[default]
+    Exists,
+    Size,
+    /// Size and SHA-1
+    Hash,
+}
+
+/// The result of [AssetFile::plan]
+#[derive(Debug, Default)]
+pub struct AssetPlan {
+    /// The objects to download keyed by hash. Includes the corrupt objects
+    pub to_download: HashMap<String, DownloadToFile>,
+    /// The hashes of the objects already present
+    pub present: Vec<String>,
+    /// The hashes of the objects present with the wrong size or hash. They are overwritten when downloaded
+    pub corrupt: Vec<String>,
+}
+impl AssetPlan {
+    /// Nothing has to be downloaded
+    pub fn is_complete(&self) -> bool {
+        self.to_download.is_empty()
+    }
+    /// The number of bytes to download
+    pub fn download_size(&self) -> usize {
+        self.to_download
+            .values()
+            .map(|download| download.download.file_size)
+            .sum()
+    }
+    fn add(
+        &mut self,
+        client: &APIClient,
+        (hash, size, location, state): (String, u32, PathBuf, FileState),
+    ) -> Result<(), url::ParseError> {
+        match state {
+            FileState::Valid => {
+                self.present.push(hash);
+                return Ok(());
             }
-            let sub = content_hash(&response.hash);
+            FileState::Corrupt { .. } => self.corrupt.push(hash.clone()),
+            FileState::Missing => {}
+        }
+        let urls = client.game_files.create_urls(
+            Endpoint::Resource,
+            &format!("{}/{hash}", content_hash(&hash)),
+        )?;
+        self.to_download.insert(
+            hash.clone(),
+            DownloadToFile::new(
+                Download {
+                    urls,
+                    file_size: size as usize,
+                    sha1: Some(hash),
+                    rate_limiter: None,
+                    client: client.clone(),
+                },
+                location,
+            ),
+        );
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        game_files::assets::{
+            data::{AssetCheck, AssetFile},
+            store::object_path,
+        },
+        test::write,
+        utils::hash::sha1_hex,
+    };
 
-            let asset_file = asset_dir.join(file_path(
-                name.as_str(),
-                (sub, &response.hash),
-                self.map_to_resources,
-            ));
-            if asset_file.exists() {
-                // Skip the files that already exist
-                continue;
+    #[tokio::test]
+    async fn plan() -> anyhow::Result<()> {
+        let server = crate::test::TestServer::start().await?;
+        let client = server.client();
+        let dir = tempfile::tempdir()?;
+        let (present, corrupt, missing) = (
+            sha1_hex(b"present"),
+            sha1_hex(b"corrupt"),
+            sha1_hex(b"missing"),
+        );
+        let index: AssetFile = serde_json::from_value(json!({
+            "objects": {
+                "a.png": { "hash": present, "size": 7 },
+                "b.png": { "hash": corrupt, "size": 7 },
+                "c.png": { "hash": missing, "size": 7 },
+                "copy_of_c.png": { "hash": missing, "size": 7 }
             }
-            let url = client
-                .game_files
-                .create_resource_url(format!("{}/{}", &sub, &response.hash));
+        }))?;
+        write(&object_path(dir.path(), &present), b"present");
+        write(&object_path(dir.path(), &corrupt), b"corrupd");
 
-            downloads.insert(
-                response.hash,
-                DownloadToFile::new(
-                    Download {
-                        url,
-                        file_size: response.size as usize,
-                        client: client.clone(),
-                    },
-                    asset_file,
-                ),
-            );
-        }
-        Ok(downloads)
+        let plan = index.plan(&client, dir.path(), AssetCheck::Size).await?;
+        assert_eq!(plan.present.len(), 2);
+        assert_eq!(plan.to_download.len(), 1);
+        assert_eq!(plan.download_size(), 7);
+
+        let plan = index.plan(&client, dir.path(), AssetCheck::Hash).await?;
+        assert_eq!(plan.present, vec![present]);
+        assert_eq!(plan.corrupt, vec![corrupt.clone()]);
+        assert_eq!(plan.to_download.len(), 2);
+
+        server.insert(
+            &format!("/resources/{}/{corrupt}", &corrupt[..2]),
+            b"corrupt".to_vec(),
+        );
+        let download = plan.to_download[&corrupt].clone();
+        download.download().await?;
+        assert_eq!(
+            std::fs::read(object_path(dir.path(), &corrupt))?,
+            b"corrupt"
+        );
+        Ok(())
     }
 }
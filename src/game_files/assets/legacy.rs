@@ -0,0 +1,249 @@
+//! Versions before 1.7.3 read assets by their name instead of from the hashed object store.
+//!
+//! Assets are always downloaded into `objects/`. [AssetStore::materialize] then builds the tree these versions expect.
+//! - `virtual` indexes (`legacy`) read from `{assets}/virtual/{index_id}`
+//! - `map_to_resources` indexes (`pre-1.6`) read from `{game_directory}/resources`
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::{copy, create_dir_all, hard_link, remove_file};
+use tracing::warn;
+
+use crate::{
+    game_files::assets::{
+        data::AssetFile,
+        store::{object_path, AssetStore},
+    },
+    utils::hash::{check_file, FileState},
+    Error,
+};
+
+/// Where the game expects to find the assets of an [AssetFile]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AssetLayout {
+    /// Read from `objects/` by hash. 1.7.3 and newer
+    Hashed,
+    /// Read by name from `{assets}/virtual/{index_id}`
+    Virtual,
+    /// Read by name from `{game_directory}/resources`
+    Resources,
+}
+impl AssetLayout {
+    /// The value of `${game_assets}` when launching a version using this layout
+    pub fn game_assets(
+        &self,
+        assets_root: &Path,
+        index_id: &str,
+        game_directory: &Path,
+    ) -> PathBuf {
+        match self {
+            AssetLayout::Hashed => assets_root.to_path_buf(),
+            AssetLayout::Virtual => assets_root.join("virtual").join(index_id),
+            AssetLayout::Resources => game_directory.join("resources"),
+        }
+    }
+}
+impl AssetFile {
+    pub fn layout(&self) -> AssetLayout {
+        if self.map_to_resources {
+            AssetLayout::Resources
+        } else if self.is_virtual {
+            AssetLayout::Virtual
+        } else {
+            AssetLayout::Hashed
+        }
+    }
+}
+
+/// How files are placed into the virtual tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkMode {
+    /// Hard link. Copies if the link fails. Such as the tree being on another filesystem
+    #[default]
+    HardLinkOrCopy,
+    HardLink,
+    Copy,
+}
+
+/// The result of [AssetStore::materialize]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MaterializedAssets {
+    /// The value of `${game_assets}`
+    pub directory: PathBuf,
+    pub linked: usize,
+    pub copied: usize,
+    /// Files that were already in place
+    pub unchanged: usize,
+}
+
+impl AssetStore {
+    /// Builds the tree the layout of the index expects from the hashed object store. At [AssetStore::game_assets]
+    ///
+    /// Files already in place with the correct size and SHA-1 are kept. Does nothing for [AssetLayout::Hashed]
+    pub async fn materialize(&self, mode: LinkMode) -> Result<MaterializedAssets, Error> {
+        let layout = self.index().layout();
+        let mut result = MaterializedAssets {
+            directory: self.game_assets(),
+            ..Default::default()
+        };
+        if layout == AssetLayout::Hashed {
+            return Ok(result);
+        }
+        for (name, object) in &self.index().objects {
+            let relative = Path::new(name);
+            if !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            {
+                warn!(
+                    ?name,
+                    "Skipping asset with a name outside of the asset tree"
+                );
+                continue;
+            }
+            let target = result.directory.join(relative);
+            match check_file(&target, Some(object.size as u64), Some(&object.hash)).await? {
+                FileState::Valid => {
+                    result.unchanged += 1;
+                    continue;
+                }
+                FileState::Corrupt { .. } => remove_file(&target).await?,
+                FileState::Missing => {}
+            }
+            if let Some(parent) = target.parent() {
+                create_dir_all(parent).await?;
+            }
+            let source = object_path(self.root(), &object.hash);
+            match mode {
+                LinkMode::Copy => {
+                    copy(&source, &target).await?;
+                    result.copied += 1;
+                }
+                LinkMode::HardLink => {
+                    hard_link(&source, &target).await?;
+                    result.linked += 1;
+                }
+                LinkMode::HardLinkOrCopy => {
+                    if hard_link(&source, &target).await.is_ok() {
+                        result.linked += 1;
+                    } else {
+                        copy(&source, &target).await?;
+                        result.copied += 1;
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use crate::{
+        game_files::assets::{
+            data::AssetFile,
+            legacy::{AssetLayout, LinkMode},
+            store::{object_path, AssetStore},
+        },
+        utils::hash::sha1_hex,
+    };
+
+    fn store(root: &Path, index_id: &str, index: serde_json::Value) -> AssetStore {
+        for body in [b"sound".as_slice(), b"lang"] {
+            let path = object_path(root, &sha1_hex(body));
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, body).unwrap();
+        }
+        AssetStore::new(root, index_id, serde_json::from_value(index).unwrap())
+    }
+    fn objects() -> serde_json::Value {
+        json!({
+            "sound/step/grass1.ogg": { "hash": sha1_hex(b"sound"), "size": 5 },
+            "lang/en_US.lang": { "hash": sha1_hex(b"lang"), "size": 4 }
+        })
+    }
+
+    #[test]
+    fn layout() -> anyhow::Result<()> {
+        let hashed: AssetFile = serde_json::from_value(json!({ "objects": {} }))?;
+        assert_eq!(hashed.layout(), AssetLayout::Hashed);
+        let legacy: AssetFile = serde_json::from_value(json!({ "virtual": true, "objects": {} }))?;
+        assert_eq!(legacy.layout(), AssetLayout::Virtual);
+        assert_eq!(
+            AssetLayout::Virtual.game_assets(Path::new("assets"), "legacy", Path::new("game")),
+            Path::new("assets/virtual/legacy")
+        );
+        assert_eq!(
+            AssetLayout::Resources.game_assets(Path::new("assets"), "pre-1.6", Path::new("game")),
+            Path::new("game/resources")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn materialize_virtual() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path().join("assets");
+        let store = store(
+            &root,
+            "legacy",
+            json!({ "virtual": true, "objects": objects() }),
+        );
+
+        let result = store.materialize(LinkMode::default()).await?;
+        assert_eq!(result.directory, root.join("virtual/legacy"));
+        assert_eq!(result.linked, 2);
+        assert_eq!(
+            std::fs::read(result.directory.join("sound/step/grass1.ogg"))?,
+            b"sound"
+        );
+
+        let again = store.materialize(LinkMode::default()).await?;
+        assert_eq!(again.unchanged, 2);
+        assert_eq!(
+            store.resolve("sound/step/grass1.ogg"),
+            Some(result.directory.join("sound/step/grass1.ogg"))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn materialize_resources() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let game = dir.path().join("game");
+        let store = store(
+            &dir.path().join("assets"),
+            "pre-1.6",
+            json!({ "map_to_resources": true, "objects": objects() }),
+        )
+        .with_game_directory(&game);
+        std::fs::create_dir_all(game.join("resources/lang"))?;
+        std::fs::write(game.join("resources/lang/en_US.lang"), b"stale!")?;
+        // Same size as the object. Only the SHA-1 differs
+        std::fs::create_dir_all(game.join("resources/sound/step"))?;
+        std::fs::write(game.join("resources/sound/step/grass1.ogg"), b"SOUND")?;
+
+        let result = store.materialize(LinkMode::Copy).await?;
+        assert_eq!(result.directory, game.join("resources"));
+        assert_eq!(result.copied, 2);
+        assert_eq!(
+            std::fs::read(game.join("resources/lang/en_US.lang"))?,
+            b"lang"
+        );
+        assert_eq!(
+            std::fs::read(game.join("resources/sound/step/grass1.ogg"))?,
+            b"sound"
+        );
+        assert_eq!(
+            store.resolve("lang/en_US.lang"),
+            Some(game.join("resources/lang/en_US.lang"))
+        );
+        // Read from the materialized tree. Not from the object store
+        std::fs::remove_dir_all(dir.path().join("assets/objects"))?;
+        assert_eq!(store.read("lang/en_US.lang").await?, b"lang");
+        Ok(())
+    }
+}
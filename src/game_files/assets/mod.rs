@@ -1,13 +1,18 @@
 use std::path::PathBuf;
 
 use crate::{
-    game_files::assets::{asset_download::AssetDownload, data::AssetResponse},
+    game_files::{
+        assets::{asset_download::AssetDownload, data::AssetResponse},
+        Endpoint,
+    },
     utils::download::Download,
     APIClient, Error,
 };
 
 pub mod asset_download;
 pub mod data;
+pub mod legacy;
+pub mod store;
 
 /// Release contains a reference to the APIClient and the internal data gotten from the asset
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -23,14 +28,16 @@ impl Asset {
         api_client: APIClient,
     ) -> Result<AssetDownload<'_, Download>, Error> {
         let sub = content_hash(&self.data.hash);
-        let url = api_client
+        let urls = api_client
             .game_files
-            .create_resource_url(format!("{}/{}", sub, &self.data.hash));
+            .create_urls(Endpoint::Resource, &format!("{}/{}", sub, &self.data.hash))?;
         Ok(AssetDownload {
             asset: self,
             download: Download {
-                url,
+                urls,
                 file_size: self.data.size as usize,
+                sha1: Some(self.data.hash.clone()),
+                rate_limiter: None,
                 client: api_client,
             },
         })
@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+
+use tokio::fs::read;
+
+use crate::{
+    game_files::assets::{content_hash, data::AssetFile, legacy::AssetLayout},
+    utils::hash::verify_sha1,
+    Error,
+};
+
+/// The directory within the assets root holding the asset indexes
+pub static INDEXES_DIR: &str = "indexes";
+/// The directory within the assets root holding the hashed objects
+pub static OBJECTS_DIR: &str = "objects";
+
+/// Reads downloaded assets by their logical name. Such as `minecraft/lang/de_de.json`
+/// ```no_run
+/// # async fn read_lang() -> Result<(), minecraft_rs::Error> {
+/// use minecraft_rs::game_files::assets::store::AssetStore;
+///
+/// let store = AssetStore::open(".minecraft/assets", "16").await?;
+/// let german = store.read("minecraft/lang/de_de.json").await?;
+/// for sound in store.list("minecraft/sounds/ambient/") {
+///     println!("{sound}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AssetStore {
+    root: PathBuf,
+    index_id: String,
+    index: AssetFile,
+    game_directory: PathBuf,
+}
+impl AssetStore {
+    /// `root` is the assets directory the index was downloaded to.
+    ///
+    /// The game directory defaults to the parent of `root`. Such as `.minecraft` for `.minecraft/assets`
+    pub fn new(root: impl Into<PathBuf>, index_id: impl Into<String>, index: AssetFile) -> Self {
+        let root = root.into();
+        let game_directory = root.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self {
+            root,
+            index_id: index_id.into(),
+            index,
+            game_directory,
+        }
+    }
+    /// Opens the assets directory with the index stored at `indexes/{index_id}.json`
+    pub async fn open(root: impl Into<PathBuf>, index_id: &str) -> Result<Self, Error> {
+        let root = root.into();
+        let index = read(index_path(&root, index_id)).await?;
+        Ok(Self::new(root, index_id, serde_json::from_slice(&index)?))
+    }
+    /// The game directory holding `resources/` for [AssetLayout::Resources] indexes
+    pub fn with_game_directory(mut self, game_directory: impl Into<PathBuf>) -> Self {
+        self.game_directory = game_directory.into();
+        self
+    }
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+    pub fn index_id(&self) -> &str {
+        &self.index_id
+    }
+    pub fn index(&self) -> &AssetFile {
+        &self.index
+    }
+    pub fn game_directory(&self) -> &Path {
+        &self.game_directory
+    }
+    /// The value of `${game_assets}`. See [AssetLayout::game_assets]
+    pub fn game_assets(&self) -> PathBuf {
+        self.index
+            .layout()
+            .game_assets(&self.root, &self.index_id, &self.game_directory)
+    }
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.objects.contains_key(name)
+    }
+    /// Where the game reads the asset from. Based on the [layout](AssetFile::layout) of the index
+    pub fn resolve(&self, name: &str) -> Option<PathBuf> {
+        let object = self.index.objects.get(name)?;
+        match self.index.layout() {
+            AssetLayout::Hashed => Some(object_path(&self.root, &object.hash)),
+            AssetLayout::Virtual | AssetLayout::Resources => Some(self.game_assets().join(name)),
+        }
+    }
+    /// The path of the asset in the hashed object store. Regardless of the layout
+    pub fn object_path(&self, name: &str) -> Option<PathBuf> {
+        let object = self.index.objects.get(name)?;
+        Some(object_path(&self.root, &object.hash))
+    }
+    /// Reads the asset and verifies its SHA-1.
+    ///
+    /// Indexes read by name fall back to the hashed object store if the tree was not [materialized](AssetStore::materialize)
+    pub async fn read(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let object = self
+            .index
+            .objects
+            .get(name)
+            .ok_or_else(|| Error::UnknownAsset(name.to_owned()))?;
+        let location = self
+            .resolve(name)
+            .ok_or_else(|| Error::UnknownAsset(name.to_owned()))?;
+        let bytes = match read(&location).await {
+            Ok(bytes) => bytes,
+            Err(err)
+                if err.kind() == std::io::ErrorKind::NotFound
+                    && self.index.layout() != AssetLayout::Hashed =>
+            {
+                read(object_path(&self.root, &object.hash)).await?
+            }
+            Err(err) => return Err(err.into()),
+        };
+        verify_sha1(name, &bytes, &object.hash)?;
+        Ok(bytes)
+    }
+    /// The names of all assets starting with the prefix. Sorted
+    pub fn list<'a>(&'a self, prefix: &str) -> Vec<&'a str> {
+        let mut names: Vec<&str> = self
+            .index
+            .objects
+            .keys()
+            .map(String::as_str)
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// `{root}/indexes/{id}.json`
+pub fn index_path(root: &Path, index_id: &str) -> PathBuf {
+    root.join(INDEXES_DIR).join(format!("{index_id}.json"))
+}
+/// `{root}/objects/{xx}/{hash}`
+pub fn object_path(root: &Path, hash: &str) -> PathBuf {
+    root.join(OBJECTS_DIR).join(content_hash(hash)).join(hash)
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        game_files::assets::{
+            data::AssetFile,
+            store::{index_path, object_path, AssetStore},
+        },
+        test::write,
+        utils::hash::sha1_hex,
+        Error,
+    };
+
+    fn index(map_to_resources: bool) -> AssetFile {
+        serde_json::from_value(json!({
+            "map_to_resources": map_to_resources,
+            "objects": {
+                "minecraft/lang/de_de.json": { "hash": sha1_hex(b"{}"), "size": 2 },
+                "minecraft/lang/en_us.json": { "hash": sha1_hex(b"{ }"), "size": 3 },
+                "pack.mcmeta": { "hash": sha1_hex(b"pack"), "size": 4 }
+            }
+        }))
+        .unwrap()
+    }
+    #[tokio::test]
+    async fn hashed() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        write(
+            &index_path(dir.path(), "16"),
+            &serde_json::to_vec(&index(false))?,
+        );
+        write(&object_path(dir.path(), &sha1_hex(b"{}")), b"{}");
+        write(&object_path(dir.path(), &sha1_hex(b"pack")), b"corrupt");
+
+        let store = AssetStore::open(dir.path(), "16").await?;
+        assert_eq!(
+            store.resolve("minecraft/lang/de_de.json"),
+            Some(object_path(dir.path(), &sha1_hex(b"{}")))
+        );
+        assert_eq!(store.read("minecraft/lang/de_de.json").await?, b"{}");
+        assert!(matches!(
+            store.read("pack.mcmeta").await,
+            Err(Error::HashMismatch(_))
+        ));
+        assert!(matches!(
+            store.read("missing.png").await,
+            Err(Error::UnknownAsset(_))
+        ));
+        assert_eq!(
+            store.list("minecraft/lang/"),
+            vec!["minecraft/lang/de_de.json", "minecraft/lang/en_us.json"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn map_to_resources() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path().join("assets");
+        write(&dir.path().join("resources/pack.mcmeta"), b"pack");
+        write(&object_path(&root, &sha1_hex(b"{}")), b"{}");
+
+        let store = AssetStore::new(&root, "pre-1.6", index(true));
+        assert_eq!(store.game_directory(), dir.path());
+        assert_eq!(
+            store.resolve("pack.mcmeta"),
+            Some(dir.path().join("resources/pack.mcmeta"))
+        );
+        assert_eq!(store.read("pack.mcmeta").await?, b"pack");
+        // Not found by name. Read from the object store instead
+        assert_eq!(store.read("minecraft/lang/de_de.json").await?, b"{}");
+        Ok(())
+    }
+}
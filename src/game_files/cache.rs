@@ -0,0 +1,298 @@
+use std::path::{Path, PathBuf};
+
+use reqwest::{
+    header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::fs::read;
+use tracing::{debug, warn};
+
+use crate::{
+    game_files::{
+        assets::data::AssetFile,
+        release::data::{AssetIndex, ReleaseData},
+        version_manifest::{Version, VersionManifest},
+        Endpoint, VERSION_MANIFEST_PATH,
+    },
+    http::IntoResult,
+    utils::{hash::sha1_hex, send_with_failover, write_atomic},
+    APIClient, Error,
+};
+
+static MANIFEST_FILE: &str = "version_manifest_v2.json";
+static MANIFEST_VALIDATORS_FILE: &str = "version_manifest_v2.validators.json";
+static RELEASES_DIR: &str = "releases";
+static ASSET_INDEXES_DIR: &str = "asset_indexes";
+
+/// An on disk cache for the launcher metadata.
+///
+/// The version manifest is revalidated with `ETag` and `If-Modified-Since`.
+/// Release JSONs and asset indexes never change for a SHA-1 so they are only downloaded once.
+/// ```no_run
+/// # async fn cached(client: minecraft_rs::APIClient) -> Result<(), minecraft_rs::Error> {
+/// use minecraft_rs::game_files::cache::MetadataCache;
+///
+/// let cache = MetadataCache::new(".cache/minecraft");
+/// let manifest = cache.version_manifest(&client).await?;
+/// let release = cache
+///     .release(&client, manifest.get_latest_release())
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetadataCache {
+    /// The directory the cache is stored in
+    pub directory: PathBuf,
+    /// Never make requests. Only serve cached copies
+    pub offline: bool,
+}
+
+/// The validators Mojang sent with the cached version manifest
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl MetadataCache {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            offline: false,
+        }
+    }
+    /// Sets if the cache should only serve cached copies
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// The version manifest. Revalidated against the cached copy.
+    ///
+    /// The cached copy is served when offline or when Mojang can not be reached
+    pub async fn version_manifest(&self, client: &APIClient) -> Result<VersionManifest, Error> {
+        let location = self.directory.join(MANIFEST_FILE);
+        let validators_location = self.directory.join(MANIFEST_VALIDATORS_FILE);
+        let cached = read(&location).await.ok();
+        if self.offline {
+            let cached = cached.ok_or_else(|| Error::NotCached(MANIFEST_FILE.to_owned()))?;
+            return parse_manifest(client, &cached).map_err(Error::from);
+        }
+        let validators = match &cached {
+            Some(_) => read_json::<Validators>(&validators_location)
+                .await
+                .unwrap_or_default(),
+            None => Validators::default(),
+        };
+        let urls = client
+            .game_files
+            .create_urls(Endpoint::LauncherMeta, VERSION_MANIFEST_PATH)?;
+        let request = |url| {
+            let mut request = client.http_client.get(url);
+            if let Some(etag) = &validators.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+            request
+        };
+        let response = match send_with_failover(&urls, request).await {
+            Ok((response, _)) => response,
+            Err(err) if err.is_connection_error() && cached.is_some() => {
+                warn!(
+                    ?err,
+                    "Could not reach the launcher meta. Using the cached manifest"
+                );
+                return parse_manifest(client, &cached.unwrap_or_default()).map_err(Error::from);
+            }
+            Err(err) => return Err(err),
+        };
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                debug!("Version manifest not modified");
+                return parse_manifest(client, &cached).map_err(Error::from);
+            }
+        }
+        let response = response.into_result::<Error>().await?;
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        let bytes = response.bytes().await?;
+        let manifest = parse_manifest(client, &bytes)?;
+        write_atomic(&location, &bytes).await?;
+        write_atomic(&validators_location, &serde_json::to_vec(&validators)?).await?;
+        Ok(manifest)
+    }
+
+    /// The release data for the version. Stored by [Version::sha1]
+    pub async fn release(
+        &self,
+        client: &APIClient,
+        version: &Version,
+    ) -> Result<ReleaseData, Error> {
+        let location = self
+            .directory
+            .join(RELEASES_DIR)
+            .join(format!("{}.json", version.sha1));
+        if let Some(cached) = read_verified(&location, &version.sha1).await {
+            return parse_release(client, &cached).map_err(Error::from);
+        }
+        if self.offline {
+            return Err(Error::NotCached(version.url.clone()));
+        }
+        let bytes = version.get_release_bytes(client).await?;
+        let release = parse_release(client, &bytes)?;
+        write_atomic(&location, &bytes).await?;
+        Ok(release)
+    }
+
+    /// The asset index. Stored by [AssetIndex::sha1]
+    pub async fn asset_index(
+        &self,
+        client: &APIClient,
+        index: &AssetIndex,
+    ) -> Result<AssetFile, Error> {
+        let location = self
+            .directory
+            .join(ASSET_INDEXES_DIR)
+            .join(format!("{}.json", index.sha1));
+        if let Some(cached) = read_verified(&location, &index.sha1).await {
+            return serde_json::from_slice(&cached).map_err(Error::from);
+        }
+        if self.offline {
+            return Err(Error::NotCached(index.url.clone()));
+        }
+        let bytes = index.get_asset_file_bytes(client).await?;
+        let asset_file = serde_json::from_slice(&bytes)?;
+        write_atomic(&location, &bytes).await?;
+        Ok(asset_file)
+    }
+}
+
+/// Parses the manifest as served and rewrites its URLs onto the configured bases
+fn parse_manifest(client: &APIClient, bytes: &[u8]) -> Result<VersionManifest, serde_json::Error> {
+    let mut manifest: VersionManifest = serde_json::from_slice(bytes)?;
+    manifest.rewrite_urls(&client.game_files);
+    Ok(manifest)
+}
+fn parse_release(client: &APIClient, bytes: &[u8]) -> Result<ReleaseData, serde_json::Error> {
+    let mut release: ReleaseData = serde_json::from_slice(bytes)?;
+    release.rewrite_urls(&client.game_files);
+    Ok(release)
+}
+/// Reads the file if it exists and matches the SHA-1
+async fn read_verified(location: &Path, sha1: &str) -> Option<Vec<u8>> {
+    let bytes = read(location).await.ok()?;
+    if sha1_hex(&bytes).eq_ignore_ascii_case(sha1) {
+        debug!(?location, "Serving cached file");
+        Some(bytes)
+    } else {
+        warn!(?location, "Cached file does not match its SHA-1");
+        None
+    }
+}
+async fn read_json<T: DeserializeOwned>(location: &Path) -> Option<T> {
+    let bytes = read(location).await.ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+#[cfg(test)]
+mod tests {
+    use crate::{game_files::cache::MetadataCache, utils::hash::sha1_hex, Error};
+
+    #[tokio::test]
+    async fn version_manifest_revalidation() -> anyhow::Result<()> {
+        crate::test::setup();
+        let server = crate::test::TestServer::start().await?;
+        let manifest_path = "/launcher_meta/mc/game/version_manifest_v2.json";
+        let mut manifest = crate::test::version_manifest_json();
+        server.insert(manifest_path, serde_json::to_vec(&manifest)?);
+        let dir = tempfile::tempdir()?;
+        let cache = MetadataCache::new(dir.path());
+        let client = server.client();
+
+        let fetched = cache.version_manifest(&client).await?;
+        assert_eq!(fetched.latest.release, "1.20.5");
+
+        // Not modified. The cached copy is used even though it was changed on disk
+        let mut cached = manifest.clone();
+        cached["latest"]["release"] = "1.20.4".into();
+        std::fs::write(
+            dir.path().join("version_manifest_v2.json"),
+            serde_json::to_vec(&cached)?,
+        )?;
+        assert_eq!(
+            cache.version_manifest(&client).await?.latest.release,
+            "1.20.4"
+        );
+
+        // Modified on the server
+        manifest["latest"]["snapshot"] = "24w14a".into();
+        server.insert(manifest_path, serde_json::to_vec(&manifest)?);
+        assert_eq!(
+            cache.version_manifest(&client).await?.latest.snapshot,
+            "24w14a"
+        );
+
+        // Mojang can not be reached
+        let unreachable = crate::test::client("http://127.0.0.1:9");
+        assert_eq!(
+            cache.version_manifest(&unreachable).await?.latest.snapshot,
+            "24w14a"
+        );
+        let offline = cache.clone().offline(true);
+        assert_eq!(
+            offline
+                .version_manifest(&unreachable)
+                .await?
+                .latest
+                .snapshot,
+            "24w14a"
+        );
+        let empty = tempfile::tempdir()?;
+        assert!(matches!(
+            MetadataCache::new(empty.path())
+                .offline(true)
+                .version_manifest(&unreachable)
+                .await,
+            Err(Error::NotCached(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn release_by_sha1() -> anyhow::Result<()> {
+        crate::test::setup();
+        let server = crate::test::TestServer::start().await?;
+        let body = serde_json::to_vec(&crate::test::release_json("1.20.4"))?;
+        server.insert("/launcher_meta/v1/packages/1.20.4.json", body.clone());
+        let dir = tempfile::tempdir()?;
+        let cache = MetadataCache::new(dir.path());
+        let client = server.client();
+
+        let mut manifest = crate::test::version_manifest();
+        let version = manifest
+            .versions
+            .iter_mut()
+            .find(|version| version.id == "1.20.4")
+            .unwrap();
+        version.url = server.url("/launcher_meta/v1/packages/1.20.4.json");
+        version.sha1 = sha1_hex(&body);
+        assert_eq!(cache.release(&client, version).await?.id, "1.20.4");
+
+        version.url = "http://127.0.0.1:9/v1/packages/1.20.4.json".to_owned();
+        let offline = cache.offline(true);
+        assert_eq!(offline.release(&client, version).await?.id, "1.20.4");
+        Ok(())
+    }
+}
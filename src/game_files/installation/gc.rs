@@ -0,0 +1,314 @@
+//! Removes asset objects and libraries no installed version references
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use tokio::fs::{remove_dir, remove_file};
+use tracing::debug;
+
+use crate::{
+    game_files::{
+        assets::store::OBJECTS_DIR,
+        installation::{walk, Installation},
+        release::{library::Library, patch::LibraryPatches, platform::Platform},
+    },
+    Error,
+};
+
+/// The files referenced by a set of versions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReferencedFiles {
+    /// The hashes of the asset objects
+    pub objects: HashSet<String>,
+    /// Library paths relative to the libraries directory
+    pub libraries: HashSet<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum UnusedKind {
+    AssetObject,
+    Library,
+}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnusedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub kind: UnusedKind,
+}
+
+/// The result of [Installation::collect_garbage]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GarbageReport {
+    /// Nothing was deleted
+    pub dry_run: bool,
+    pub unused: Vec<UnusedFile>,
+    pub unused_bytes: u64,
+    pub kept_files: usize,
+    pub kept_bytes: u64,
+}
+impl Display for GarbageReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let action = if self.dry_run {
+            "Would remove"
+        } else {
+            "Removed"
+        };
+        write!(
+            f,
+            "{action} {} files ({} bytes). Kept {} files ({} bytes)",
+            self.unused.len(),
+            self.unused_bytes,
+            self.kept_files,
+            self.kept_bytes
+        )
+    }
+}
+
+impl Installation {
+    /// Loads the release and asset index of every version. Every library artifact is referenced regardless of the platform.
+    ///
+    /// The libraries the patches insert on the platform are referenced as well. Pass [LibraryPatches::default] without patches
+    pub async fn referenced_files<S: AsRef<str>>(
+        &self,
+        versions: &[S],
+        patches: &LibraryPatches,
+        platform: &Platform,
+    ) -> Result<ReferencedFiles, Error> {
+        let mut referenced = ReferencedFiles::default();
+        for version in versions {
+            let mut release = self.release(version.as_ref()).await?;
+            let store = self.asset_store(&release).await?;
+            referenced.objects.extend(
+                store
+                    .index()
+                    .objects
+                    .values()
+                    .map(|object| object.hash.clone()),
+            );
+            referenced.add_libraries(&release.libraries);
+            if !patches.apply(&mut release, platform).is_empty() {
+                referenced.add_libraries(&release.libraries);
+            }
+        }
+        Ok(referenced)
+    }
+    /// Finds the asset objects and libraries not referenced by `versions` and deletes them unless `dry_run`.
+    ///
+    /// Libraries inserted by the patches on the platform are kept. See [Installation::referenced_files].
+    ///
+    /// Fails without deleting anything if a release or asset index can not be read
+    pub async fn collect_garbage<S: AsRef<str>>(
+        &self,
+        versions: &[S],
+        patches: &LibraryPatches,
+        platform: &Platform,
+        dry_run: bool,
+    ) -> Result<GarbageReport, Error> {
+        let referenced = self.referenced_files(versions, patches, platform).await?;
+        let mut report = GarbageReport {
+            dry_run,
+            ..Default::default()
+        };
+        let objects = self.assets_directory().join(OBJECTS_DIR);
+        for (path, size) in walk(&objects).await? {
+            let used = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|hash| referenced.objects.contains(hash));
+            report.add(path, size, used, UnusedKind::AssetObject);
+        }
+        let libraries = self.libraries_directory();
+        for (path, size) in walk(&libraries).await? {
+            let used = path
+                .strip_prefix(&libraries)
+                .is_ok_and(|relative| referenced.libraries.contains(relative));
+            report.add(path, size, used, UnusedKind::Library);
+        }
+        if !dry_run {
+            for file in &report.unused {
+                remove_file(&file.path).await?;
+                let root = match file.kind {
+                    UnusedKind::AssetObject => &objects,
+                    UnusedKind::Library => &libraries,
+                };
+                remove_empty_parents(&file.path, root).await;
+            }
+        }
+        Ok(report)
+    }
+}
+impl ReferencedFiles {
+    fn add_libraries(&mut self, libraries: &[Library]) {
+        for library in libraries {
+            let downloads = &library.downloads;
+            self.libraries.extend(
+                downloads
+                    .artifact
+                    .iter()
+                    .chain(downloads.classifiers.values())
+                    .map(|artifact| PathBuf::from(&artifact.path)),
+            );
+        }
+    }
+}
+impl GarbageReport {
+    fn add(&mut self, path: PathBuf, size: u64, used: bool, kind: UnusedKind) {
+        if used {
+            self.kept_files += 1;
+            self.kept_bytes += size;
+        } else {
+            self.unused_bytes += size;
+            self.unused.push(UnusedFile { path, size, kind });
+        }
+    }
+}
+/// Removes the now empty directories between the file and the root
+async fn remove_empty_parents(file: &Path, root: &Path) {
+    let mut directory = file.parent();
+    while let Some(current) = directory.filter(|current| *current != root) {
+        if remove_dir(current).await.is_err() {
+            break;
+        }
+        debug!(?current, "Removed empty directory");
+        directory = current.parent();
+    }
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        game_files::{
+            assets::store::object_path,
+            installation::Installation,
+            release::{patch::LibraryPatches, platform::Platform},
+        },
+        test::write,
+        utils::hash::sha1_hex,
+    };
+
+    #[tokio::test]
+    async fn collect_garbage() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let installation = Installation::new(dir.path());
+        let mut release = crate::test::release_json("1.20.4");
+        release["libraries"] = json!([{
+            "name": "com.mojang:brigadier:1.0.18",
+            "downloads": {
+                "artifact": {
+                    "path": "com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar",
+                    "sha1": sha1_hex(b"brigadier"),
+                    "size": 9,
+                    "url": "https://libraries.minecraft.net/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar"
+                }
+            }
+        }]);
+        write(
+            &installation.release_path("1.20.4"),
+            &serde_json::to_vec(&release)?,
+        );
+        let assets = installation.assets_directory();
+        write(
+            &assets.join("indexes/1.20.4.json"),
+            &serde_json::to_vec(&json!({
+                "objects": { "icons/icon_16x16.png": { "hash": sha1_hex(b"icon"), "size": 4 } }
+            }))?,
+        );
+        write(&object_path(&assets, &sha1_hex(b"icon")), b"icon");
+        write(&object_path(&assets, &sha1_hex(b"old")), b"old");
+        let libraries = installation.libraries_directory();
+        write(
+            &libraries.join("com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar"),
+            b"brigadier",
+        );
+        write(
+            &libraries.join("org/lwjgl/lwjgl/2.9.4/lwjgl-2.9.4.jar"),
+            b"lwjgl",
+        );
+
+        let versions = installation.installed_versions().await?;
+        assert_eq!(versions, vec!["1.20.4"]);
+        let no_patches = LibraryPatches::default();
+        let linux = Platform::new("linux", "x86_64");
+        let report = installation
+            .collect_garbage(&versions, &no_patches, &linux, true)
+            .await?;
+        assert_eq!(report.unused.len(), 2);
+        assert_eq!(report.unused_bytes, 8);
+        assert_eq!(report.kept_files, 2);
+        assert!(object_path(&assets, &sha1_hex(b"old")).exists());
+
+        let report = installation
+            .collect_garbage(&versions, &no_patches, &linux, false)
+            .await?;
+        assert_eq!(report.unused.len(), 2);
+        assert!(!object_path(&assets, &sha1_hex(b"old")).exists());
+        assert!(!libraries.join("org").exists());
+        assert!(object_path(&assets, &sha1_hex(b"icon")).exists());
+        assert!(installation
+            .collect_garbage(&versions, &no_patches, &linux, false)
+            .await?
+            .unused
+            .is_empty());
+
+        assert!(installation
+            .collect_garbage(&["1.8.9"], &no_patches, &linux, true)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn keep_patched_libraries() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let installation = Installation::new(dir.path());
+        let artifact = |path: &str| json!({ "path": path, "sha1": sha1_hex(path.as_bytes()), "size": path.len(), "url": "" });
+        let mut release = crate::test::release_json("1.16.5");
+        release["libraries"] = json!([
+            { "name": "org.lwjgl:lwjgl:3.2.2", "downloads": { "artifact": artifact("lwjgl-3.2.2.jar") } }
+        ]);
+        write(
+            &installation.release_path("1.16.5"),
+            &serde_json::to_vec(&release)?,
+        );
+        write(
+            &installation.asset_index_path("1.16.5"),
+            &serde_json::to_vec(&json!({ "objects": {} }))?,
+        );
+        let libraries = installation.libraries_directory();
+        write(&libraries.join("lwjgl-3.2.2.jar"), b"x86");
+        write(
+            &libraries.join("lwjgl-3.3.1-natives-linux-arm64.jar"),
+            b"arm64",
+        );
+        let patches: LibraryPatches = serde_json::from_value(json!({
+            "patches": [{
+                "library": "org.lwjgl:lwjgl",
+                "rules": [{ "action": "allow", "os": { "name": "linux", "arch": "arm64" } }],
+                "replace": [{
+                    "name": "org.lwjgl:lwjgl:3.3.1:natives-linux-arm64",
+                    "downloads": { "artifact": artifact("lwjgl-3.3.1-natives-linux-arm64.jar") }
+                }]
+            }]
+        }))?;
+
+        let versions = ["1.16.5"];
+        let arm = Platform::new("linux", "arm64");
+        let unpatched = installation
+            .collect_garbage(&versions, &LibraryPatches::default(), &arm, true)
+            .await?;
+        assert_eq!(unpatched.unused.len(), 1);
+        let report = installation
+            .collect_garbage(&versions, &patches, &arm, false)
+            .await?;
+        assert!(report.unused.is_empty());
+        assert_eq!(report.kept_files, 2);
+        assert!(libraries
+            .join("lwjgl-3.3.1-natives-linux-arm64.jar")
+            .exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+//! The layout of an installed game directory. Such as `.minecraft`
+//!
+//! - `versions/{id}/{id}.json` and `versions/{id}/{id}.jar`
+//! - `libraries/{artifact path}`
+//! - `assets/indexes/{index id}.json` and `assets/objects/{xx}/{hash}`
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use tokio::fs::{read, read_dir};
+
+use crate::{
+    game_files::{
+        assets::store::{index_path, AssetStore},
+        release::{data::ReleaseData, library::Artifact, platform::Platform},
+    },
+    Error,
+};
+
+pub mod gc;
+pub mod plan;
+pub mod verify;
+
+/// A game directory shared by the installed versions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Installation {
+    pub root: PathBuf,
+}
+impl Installation {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+    pub fn versions_directory(&self) -> PathBuf {
+        self.root.join("versions")
+    }
+    pub fn libraries_directory(&self) -> PathBuf {
+        self.root.join("libraries")
+    }
+    pub fn assets_directory(&self) -> PathBuf {
+        self.root.join("assets")
+    }
+    /// `runtime/{component}/{platform}/{component}`. `platform` is the [runtime key](crate::game_files::release::platform::Platform::java_runtime_key)
+    pub fn runtime_directory(&self, component: &str, platform: &str) -> PathBuf {
+        self.root
+            .join("runtime")
+            .join(component)
+            .join(platform)
+            .join(component)
+    }
+    /// `versions/{id}/{id}.json`
+    pub fn release_path(&self, id: &str) -> PathBuf {
+        self.versions_directory()
+            .join(id)
+            .join(format!("{id}.json"))
+    }
+    /// `versions/{id}/{id}.jar`
+    pub fn client_jar_path(&self, id: &str) -> PathBuf {
+        self.versions_directory().join(id).join(format!("{id}.jar"))
+    }
+    /// `versions/{id}/{id}-natives`
+    pub fn natives_directory(&self, id: &str) -> PathBuf {
+        self.versions_directory()
+            .join(id)
+            .join(format!("{id}-natives"))
+    }
+    /// `assets/indexes/{id}.json`
+    pub fn asset_index_path(&self, id: &str) -> PathBuf {
+        index_path(&self.assets_directory(), id)
+    }
+    /// `libraries/{path}`
+    pub fn library_path(&self, artifact: &Artifact) -> PathBuf {
+        self.libraries_directory().join(&artifact.path)
+    }
+    /// The ids of the versions with a release json. Sorted
+    pub async fn installed_versions(&self) -> Result<Vec<String>, Error> {
+        let mut versions = Vec::new();
+        let mut entries = match read_dir(self.versions_directory()).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(versions),
+            Err(err) => return Err(err.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let Some(id) = entry.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+            if self.release_path(&id).is_file() {
+                versions.push(id);
+            }
+        }
+        versions.sort_unstable();
+        Ok(versions)
+    }
+    /// Reads `versions/{id}/{id}.json`
+    pub async fn release(&self, id: &str) -> Result<ReleaseData, Error> {
+        let bytes = read(self.release_path(id)).await?;
+        serde_json::from_slice(&bytes).map_err(Error::from)
+    }
+    /// The libraries used on the platform followed by the client jar. Apply [patches](crate::game_files::release::patch) to the release first
+    pub fn classpath(&self, release: &ReleaseData, platform: &Platform) -> Vec<PathBuf> {
+        let mut classpath: Vec<PathBuf> = Vec::new();
+        for artifact in release
+            .libraries
+            .iter()
+            .filter(|library| library.applies_to(platform))
+            .filter_map(|library| library.downloads.artifact.as_ref())
+        {
+            let path = self.library_path(artifact);
+            if !classpath.contains(&path) {
+                classpath.push(path);
+            }
+        }
+        classpath.push(self.client_jar_path(&release.id));
+        classpath
+    }
+    /// The native jars to extract into the [natives directory](Installation::natives_directory)
+    pub fn natives(&self, release: &ReleaseData, platform: &Platform) -> Vec<NativeLibrary> {
+        release
+            .libraries
+            .iter()
+            .filter(|library| library.applies_to(platform))
+            .filter_map(|library| {
+                Some(NativeLibrary {
+                    path: self.library_path(library.native_artifact(platform)?),
+                    exclude: library
+                        .extract
+                        .as_ref()
+                        .map(|extract| extract.exclude.clone())
+                        .unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+    /// Opens the asset index used by the release
+    pub async fn asset_store(&self, release: &ReleaseData) -> Result<AssetStore, Error> {
+        Ok(
+            AssetStore::open(self.assets_directory(), &release.asset_index.id)
+                .await?
+                .with_game_directory(&self.root),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeLibrary {
+    pub path: PathBuf,
+    /// Path prefixes not extracted. Such as `META-INF/`
+    pub exclude: Vec<String>,
+}
+
+/// Every file below the directory with its size. Empty if the directory does not exist
+pub(crate) async fn walk(directory: &Path) -> Result<Vec<(PathBuf, u64)>, Error> {
+    let mut files = Vec::new();
+    let mut directories = vec![directory.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        let mut entries = match read_dir(&directory).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                directories.push(entry.path());
+            } else if file_type.is_file() {
+                files.push((entry.path(), entry.metadata().await?.len()));
+            }
+        }
+    }
+    files.sort_unstable();
+    Ok(files)
+}
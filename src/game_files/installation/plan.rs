@@ -0,0 +1,517 @@
+//! Lists every file an install would fetch without downloading any of them
+use std::path::PathBuf;
+
+use serde::Serialize;
+use tokio::fs::read;
+use tracing::warn;
+
+use crate::{
+    game_files::{
+        assets::data::AssetFile,
+        installation::{
+            verify::{check_files, download_files, ExpectedFile, FileKind},
+            Installation,
+        },
+        java_runtime::{RuntimeFile, RuntimeVersion},
+        release::{data::ReleaseData, platform::Platform},
+    },
+    utils::hash::{sha1_hex, FileState},
+    APIClient, Error,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlanOptions {
+    /// Include the Java runtime Mojang provides for the platform
+    pub java_runtime: bool,
+    /// Hash existing files. Otherwise files with the correct size are satisfied
+    pub check_hashes: bool,
+}
+
+/// The result of [Installation::plan_install]. URLs point at the configured bases.
+///
+/// Paths are absolute. Joined onto the root of the [Installation] the plan was made for. Install with [Installation::install]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InstallPlan {
+    pub version: String,
+    pub platform: Platform,
+    /// None if not requested or Mojang provides no runtime for the platform
+    pub java_runtime: Option<RuntimeVersion>,
+    pub to_download: Vec<ExpectedFile>,
+    /// Files already present locally
+    pub satisfied: Vec<ExpectedFile>,
+    pub download_bytes: u64,
+    pub satisfied_bytes: u64,
+    /// Java runtime files marked executable by [Installation::install]. Downloaded or satisfied
+    pub executables: Vec<PathBuf>,
+    /// Symbolic links of the Java runtime created by [Installation::install]
+    pub links: Vec<RuntimeLink>,
+}
+
+/// A symbolic link listed by the runtime manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuntimeLink {
+    pub path: PathBuf,
+    /// Relative to the directory of the link
+    pub target: String,
+}
+
+impl Installation {
+    /// Lists the client, libraries, natives, log config, asset index, assets and optionally the Java runtime the release needs on the platform.
+    ///
+    /// Only metadata is requested. The asset index is read locally if present and intact
+    pub async fn plan_install(
+        &self,
+        client: &APIClient,
+        release: &ReleaseData,
+        platform: &Platform,
+        options: PlanOptions,
+    ) -> Result<InstallPlan, Error> {
+        let mut files = self.expected_files(release, platform);
+        if let Some(logging) = release.client_logging() {
+            files.push(ExpectedFile {
+                kind: FileKind::Logging,
+                path: logging.config_path(self.assets_directory()),
+                url: logging.file.url.clone(),
+                sha1: logging.file.sha1.clone(),
+                size: logging.file.size,
+            });
+        }
+        let index = self.local_or_remote_asset_index(client, release).await?;
+        files.extend(self.asset_files(client, &index)?);
+
+        let mut java_runtime = None;
+        let mut executables = Vec::new();
+        let mut links = Vec::new();
+        if options.java_runtime {
+            let component = &release.java_version.component;
+            let runtimes = client.java_runtimes().await?;
+            match (
+                runtimes.select(platform, component),
+                runtimes.runtime_key(platform, component),
+            ) {
+                (Some(runtime), Some(key)) => {
+                    let directory = self.runtime_directory(component, key);
+                    let manifest = runtime.get_manifest(client).await?;
+                    for (path, file) in manifest.files {
+                        let path = directory.join(path);
+                        match file {
+                            RuntimeFile::File {
+                                downloads,
+                                executable,
+                            } => {
+                                if executable {
+                                    executables.push(path.clone());
+                                }
+                                files.push(ExpectedFile {
+                                    kind: FileKind::Runtime,
+                                    path,
+                                    url: downloads.raw.url,
+                                    sha1: downloads.raw.sha1,
+                                    size: downloads.raw.size,
+                                });
+                            }
+                            RuntimeFile::Link { target } => {
+                                links.push(RuntimeLink { path, target })
+                            }
+                            RuntimeFile::Directory => {}
+                        }
+                    }
+                    executables.sort();
+                    links.sort_by(|a, b| a.path.cmp(&b.path));
+                    java_runtime = Some(runtime.version.clone());
+                }
+                _ => warn!(?platform, component, "No Java runtime for the platform"),
+            }
+        }
+
+        for file in &mut files {
+            client.game_files.rewrite_in_place(&mut file.url);
+        }
+        let mut plan = InstallPlan {
+            version: release.id.clone(),
+            platform: platform.clone(),
+            java_runtime,
+            to_download: Vec::new(),
+            satisfied: Vec::new(),
+            download_bytes: 0,
+            satisfied_bytes: 0,
+            executables,
+            links,
+        };
+        for (file, state) in check_files(files, options.check_hashes).await? {
+            if state == FileState::Valid {
+                plan.satisfied_bytes += file.size;
+                plan.satisfied.push(file);
+            } else {
+                plan.download_bytes += file.size;
+                plan.to_download.push(file);
+            }
+        }
+        plan.to_download.sort_by(|a, b| a.path.cmp(&b.path));
+        plan.satisfied.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(plan)
+    }
+    /// Downloads the files of the plan. Then marks the executables and creates the links of the Java runtime.
+    ///
+    /// The plan may target another platform than the host. Permissions and links are only applied on unix hosts. Returns the files downloaded
+    pub async fn install(
+        &self,
+        client: &APIClient,
+        plan: &InstallPlan,
+    ) -> Result<Vec<ExpectedFile>, Error> {
+        let downloaded = download_files(client, plan.to_download.iter().cloned()).await?;
+        apply_runtime_metadata(plan).await?;
+        Ok(downloaded)
+    }
+    async fn local_or_remote_asset_index(
+        &self,
+        client: &APIClient,
+        release: &ReleaseData,
+    ) -> Result<AssetFile, Error> {
+        let asset_index = &release.asset_index;
+        if let Ok(bytes) = read(self.asset_index_path(&asset_index.id)).await {
+            if sha1_hex(&bytes).eq_ignore_ascii_case(&asset_index.sha1) {
+                return serde_json::from_slice(&bytes).map_err(Error::from);
+            }
+        }
+        asset_index.get_asset_file(client).await
+    }
+}
+#[cfg(unix)]
+async fn apply_runtime_metadata(plan: &InstallPlan) -> Result<(), Error> {
+    use std::{io, os::unix::fs::PermissionsExt, path::Path};
+
+    use tokio::fs::{create_dir_all, metadata, read_link, remove_file, set_permissions, symlink};
+
+    for path in &plan.executables {
+        let mut permissions = metadata(path).await?.permissions();
+        permissions.set_mode(permissions.mode() | 0o111);
+        set_permissions(path, permissions).await?;
+    }
+    for link in &plan.links {
+        if read_link(&link.path)
+            .await
+            .is_ok_and(|target| target == Path::new(&link.target))
+        {
+            continue;
+        }
+        match remove_file(&link.path).await {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+        if let Some(parent) = link.path.parent() {
+            create_dir_all(parent).await?;
+        }
+        symlink(&link.target, &link.path).await?;
+    }
+    Ok(())
+}
+#[cfg(not(unix))]
+async fn apply_runtime_metadata(plan: &InstallPlan) -> Result<(), Error> {
+    if !plan.executables.is_empty() || !plan.links.is_empty() {
+        warn!(
+            version = plan.version,
+            "Executable permissions and links of the Java runtime are only applied on unix hosts"
+        );
+    }
+    Ok(())
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        game_files::{
+            installation::{plan::PlanOptions, verify::FileKind, Installation},
+            java_runtime::JAVA_RUNTIMES_PATH,
+            release::{data::ReleaseData, platform::Platform},
+        },
+        test::write,
+        utils::hash::sha1_hex,
+    };
+
+    #[tokio::test]
+    async fn plan_install() -> anyhow::Result<()> {
+        let server = crate::test::TestServer::start().await?;
+        let client = server.client();
+        let index = serde_json::to_vec(&json!({
+            "objects": { "icons/icon_16x16.png": { "hash": sha1_hex(b"icon"), "size": 4 } }
+        }))?;
+        server.insert("/index.json", index.clone());
+        let runtime_manifest = serde_json::to_vec(&json!({
+            "files": {
+                "bin": { "type": "directory" },
+                "bin/java": {
+                    "type": "file",
+                    "executable": true,
+                    "downloads": { "raw": { "sha1": sha1_hex(b"java"), "size": 4, "url": "https://piston-data.mojang.com/v1/objects/java" } }
+                }
+            }
+        }))?;
+        server.insert(
+            &format!("/launcher_meta/{JAVA_RUNTIMES_PATH}"),
+            serde_json::to_vec(&json!({
+                "linux": { "jre-legacy": [{
+                    "availability": { "group": 1, "progress": 100 },
+                    "manifest": { "sha1": sha1_hex(&runtime_manifest), "size": runtime_manifest.len(), "url": server.url("/runtime.json") },
+                    "version": { "name": "8u51", "released": "2015-07-15T00:00:00+00:00" }
+                }] }
+            }))?,
+        );
+        server.insert("/runtime.json", runtime_manifest);
+
+        let mut release = crate::test::release_json("1.12.2");
+        release["downloads"]["client"] = json!({
+            "sha1": sha1_hex(b"client"),
+            "size": 6,
+            "url": "https://piston-data.mojang.com/v1/objects/client.jar"
+        });
+        release["assetIndex"] = json!({
+            "id": "1.12",
+            "sha1": sha1_hex(&index),
+            "size": index.len(),
+            "url": server.url("/index.json")
+        });
+        release["libraries"] = json!([{
+            "name": "com.mojang:realms:1.10.22",
+            "downloads": { "artifact": {
+                "path": "com/mojang/realms/1.10.22/realms-1.10.22.jar",
+                "sha1": sha1_hex(b"realms"),
+                "size": 6,
+                "url": "https://libraries.minecraft.net/com/mojang/realms/1.10.22/realms-1.10.22.jar"
+            } }
+        }]);
+        let release: ReleaseData = serde_json::from_value(release)?;
+
+        let dir = tempfile::tempdir()?;
+        let installation = Installation::new(dir.path());
+        write(&installation.client_jar_path("1.12.2"), b"client");
+
+        let options = PlanOptions {
+            java_runtime: true,
+            ..Default::default()
+        };
+        let plan = installation
+            .plan_install(
+                &client,
+                &release,
+                &Platform::new("linux", "x86_64"),
+                options,
+            )
+            .await?;
+        assert_eq!(plan.java_runtime.as_ref().unwrap().name, "8u51");
+        assert_eq!(plan.satisfied.len(), 1);
+        assert_eq!(plan.satisfied_bytes, 6);
+        let mut kinds: Vec<FileKind> = plan.to_download.iter().map(|file| file.kind).collect();
+        kinds.sort_by_key(|kind| *kind as u8);
+        assert_eq!(
+            kinds,
+            vec![
+                FileKind::Library,
+                FileKind::AssetIndex,
+                FileKind::Asset,
+                FileKind::Runtime
+            ]
+        );
+        assert_eq!(plan.download_bytes, 6 + index.len() as u64 + 4 + 4);
+        let runtime = plan
+            .to_download
+            .iter()
+            .find(|file| file.kind == FileKind::Runtime)
+            .unwrap();
+        assert_eq!(runtime.url, server.url("/launcher_meta/v1/objects/java"));
+        assert!(runtime
+            .path
+            .ends_with("runtime/jre-legacy/linux/jre-legacy/bin/java"));
+        let json = serde_json::to_value(&plan)?;
+        assert_eq!(json["to_download"][0]["kind"], "asset_index");
+
+        let arm = installation
+            .plan_install(&client, &release, &Platform::new("linux", "arm64"), options)
+            .await?;
+        assert!(arm.java_runtime.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cross_platform_install() -> anyhow::Result<()> {
+        let server = crate::test::TestServer::start().await?;
+        let client = server.client();
+        let file = |path: &str, body: &[u8]| {
+            server.insert(&format!("/{path}"), body.to_vec());
+            json!({ "path": path, "sha1": sha1_hex(body), "size": body.len(), "url": server.url(&format!("/{path}")) })
+        };
+        let index = serde_json::to_vec(&json!({ "objects": {} }))?;
+        server.insert("/index.json", index.clone());
+        let runtime_manifest = serde_json::to_vec(&json!({
+            "files": {
+                "bin/java.exe": {
+                    "type": "file",
+                    "executable": true,
+                    "downloads": { "raw": file("java.exe", b"java") }
+                }
+            }
+        }))?;
+        server.insert(
+            &format!("/launcher_meta/{JAVA_RUNTIMES_PATH}"),
+            serde_json::to_vec(&json!({
+                "windows-x64": { "jre-legacy": [{
+                    "manifest": { "sha1": sha1_hex(&runtime_manifest), "size": runtime_manifest.len(), "url": server.url("/runtime.json") },
+                    "version": { "name": "8u51", "released": "2015-07-15T00:00:00+00:00" }
+                }] }
+            }))?,
+        );
+        server.insert("/runtime.json", runtime_manifest);
+
+        let mut release = crate::test::release_json("1.12.2");
+        release["downloads"]["client"] = file("client.jar", b"client");
+        release["assetIndex"] = json!({
+            "id": "1.12",
+            "sha1": sha1_hex(&index),
+            "size": index.len(),
+            "url": server.url("/index.json")
+        });
+        release["libraries"] = json!([
+            {
+                "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+                "downloads": { "classifiers": {
+                    "natives-linux": file("natives-linux.jar", b"linux"),
+                    "natives-windows": file("natives-windows.jar", b"windows")
+                } },
+                "natives": { "linux": "natives-linux", "windows": "natives-windows" }
+            },
+            {
+                "name": "ca.weblite:java-objc-bridge:1.0.0",
+                "downloads": { "artifact": file("java-objc-bridge.jar", b"objc") },
+                "rules": [{ "action": "allow", "os": { "name": "osx" } }]
+            }
+        ]);
+        let release: ReleaseData = serde_json::from_value(release)?;
+        let windows = Platform::new("windows", "x86_64");
+        assert_eq!(release.get_libraries_to_download(&windows).len(), 1);
+        assert_eq!(
+            release.get_natives_to_download(&windows)[0].path,
+            "natives-windows.jar"
+        );
+
+        let dir = tempfile::tempdir()?;
+        let installation = Installation::new(dir.path());
+        let options = PlanOptions {
+            java_runtime: true,
+            ..Default::default()
+        };
+        let plan = installation
+            .plan_install(&client, &release, &windows, options)
+            .await?;
+        let installed = installation.install(&client, &plan).await?;
+        assert_eq!(installed.len(), plan.to_download.len());
+        assert_eq!(
+            std::fs::read(
+                installation
+                    .libraries_directory()
+                    .join("natives-windows.jar")
+            )?,
+            b"windows"
+        );
+        assert!(!installation
+            .libraries_directory()
+            .join("natives-linux.jar")
+            .exists());
+        assert!(!installation
+            .libraries_directory()
+            .join("java-objc-bridge.jar")
+            .exists());
+        assert!(installation
+            .runtime_directory("jre-legacy", "windows-x64")
+            .join("bin/java.exe")
+            .is_file());
+
+        let plan = installation
+            .plan_install(&client, &release, &windows, options)
+            .await?;
+        assert!(plan.to_download.is_empty());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn install_runtime() -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let server = crate::test::TestServer::start().await?;
+        let client = server.client();
+        let file = |path: &str, body: &[u8]| {
+            server.insert(&format!("/{path}"), body.to_vec());
+            json!({ "sha1": sha1_hex(body), "size": body.len(), "url": server.url(&format!("/{path}")) })
+        };
+        let index = serde_json::to_vec(&json!({ "objects": {} }))?;
+        server.insert("/index.json", index.clone());
+        let runtime_manifest = serde_json::to_vec(&json!({
+            "files": {
+                "bin": { "type": "directory" },
+                "bin/java": { "type": "file", "executable": true, "downloads": { "raw": file("java", b"java") } },
+                "lib/libjli.so": { "type": "file", "executable": false, "downloads": { "raw": file("libjli.so", b"jli") } },
+                "lib/jli/libjli.so": { "type": "link", "target": "../libjli.so" }
+            }
+        }))?;
+        server.insert(
+            &format!("/launcher_meta/{JAVA_RUNTIMES_PATH}"),
+            serde_json::to_vec(&json!({
+                "mac-os": { "jre-legacy": [{
+                    "manifest": { "sha1": sha1_hex(&runtime_manifest), "size": runtime_manifest.len(), "url": server.url("/runtime.json") },
+                    "version": { "name": "8u51", "released": "2015-07-15T00:00:00+00:00" }
+                }] }
+            }))?,
+        );
+        server.insert("/runtime.json", runtime_manifest);
+        let mut release = crate::test::release_json("1.12.2");
+        release["downloads"]["client"] = file("client.jar", b"client");
+        release["assetIndex"] = json!({
+            "id": "1.12",
+            "sha1": sha1_hex(&index),
+            "size": index.len(),
+            "url": server.url("/index.json")
+        });
+        let release: ReleaseData = serde_json::from_value(release)?;
+
+        let dir = tempfile::tempdir()?;
+        let installation = Installation::new(dir.path());
+        let osx = Platform::new("osx", "x86_64");
+        let options = PlanOptions {
+            java_runtime: true,
+            ..Default::default()
+        };
+        let plan = installation
+            .plan_install(&client, &release, &osx, options)
+            .await?;
+        let runtime = installation.runtime_directory("jre-legacy", "mac-os");
+        assert_eq!(plan.executables, vec![runtime.join("bin/java")]);
+        assert_eq!(plan.links.len(), 1);
+        assert_eq!(plan.links[0].path, runtime.join("lib/jli/libjli.so"));
+        installation.install(&client, &plan).await?;
+
+        let mode = |path: &str| -> std::io::Result<u32> {
+            Ok(std::fs::metadata(runtime.join(path))?.permissions().mode())
+        };
+        assert_ne!(mode("bin/java")? & 0o111, 0);
+        assert_eq!(mode("lib/libjli.so")? & 0o111, 0);
+        assert_eq!(
+            std::fs::read_link(runtime.join("lib/jli/libjli.so"))?,
+            std::path::Path::new("../libjli.so")
+        );
+        assert_eq!(std::fs::read(runtime.join("lib/jli/libjli.so"))?, b"jli");
+
+        // Everything is satisfied. Permissions and links are applied again
+        std::fs::set_permissions(
+            runtime.join("bin/java"),
+            std::fs::Permissions::from_mode(0o644),
+        )?;
+        let plan = installation
+            .plan_install(&client, &release, &osx, options)
+            .await?;
+        assert!(plan.to_download.is_empty());
+        installation.install(&client, &plan).await?;
+        assert_ne!(mode("bin/java")? & 0o111, 0);
+        Ok(())
+    }
+}
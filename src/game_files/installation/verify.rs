@@ -0,0 +1,397 @@
+//! Checks an installed version against its [ReleaseData] and re-downloads the files that failed
+use std::{collections::HashSet, path::PathBuf};
+
+use serde::Serialize;
+use tokio::{fs::remove_file, task::JoinSet};
+use tracing::debug;
+
+use crate::{
+    error::HashMismatch,
+    game_files::{
+        assets::{content_hash, data::AssetFile, store::object_path},
+        installation::{walk, Installation},
+        release::{data::ReleaseData, library::Artifact, platform::Platform},
+    },
+    utils::{
+        download::Download,
+        hash::{check_file, sha1_file, FileState},
+    },
+    APIClient, Error,
+};
+
+/// The number of files hashed or downloaded at once
+const CONCURRENCY: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    Client,
+    Library,
+    Native,
+    AssetIndex,
+    Asset,
+    Logging,
+    Runtime,
+}
+/// A file an installed version requires
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExpectedFile {
+    pub kind: FileKind,
+    pub path: PathBuf,
+    pub url: String,
+    pub sha1: String,
+    pub size: u64,
+}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CorruptFile {
+    #[serde(flatten)]
+    pub file: ExpectedFile,
+    pub actual_size: u64,
+    /// None if the size already did not match
+    pub actual_sha1: Option<String>,
+}
+
+/// The result of [Installation::verify]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VerificationReport {
+    pub version: String,
+    /// The number of files checked
+    pub checked: usize,
+    pub missing: Vec<ExpectedFile>,
+    pub corrupt: Vec<CorruptFile>,
+    /// Files in the version directory that do not belong to the version. The natives directory is not included
+    pub extra: Vec<PathBuf>,
+}
+impl VerificationReport {
+    /// No files are missing or corrupt
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.corrupt.is_empty()
+    }
+    /// The missing and corrupt files
+    pub fn failed(&self) -> impl Iterator<Item = &ExpectedFile> {
+        self.missing
+            .iter()
+            .chain(self.corrupt.iter().map(|corrupt| &corrupt.file))
+    }
+    fn record(&mut self, file: ExpectedFile, state: FileState) {
+        self.checked += 1;
+        match state {
+            FileState::Valid => {}
+            FileState::Missing => self.missing.push(file),
+            FileState::Corrupt {
+                actual_size,
+                actual_sha1,
+            } => self.corrupt.push(CorruptFile {
+                file,
+                actual_size,
+                actual_sha1,
+            }),
+        }
+    }
+}
+
+impl Installation {
+    /// The client jar, libraries, natives and asset index the release requires on the platform.
+    ///
+    /// The asset objects are listed by the asset index. See [Installation::verify]
+    pub fn expected_files(&self, release: &ReleaseData, platform: &Platform) -> Vec<ExpectedFile> {
+        let client = &release.downloads.client;
+        let mut files = vec![
+            ExpectedFile {
+                kind: FileKind::Client,
+                path: self.client_jar_path(&release.id),
+                url: client.url.clone(),
+                sha1: client.sha1.clone(),
+                size: client.size,
+            },
+            ExpectedFile {
+                kind: FileKind::AssetIndex,
+                path: self.asset_index_path(&release.asset_index.id),
+                url: release.asset_index.url.clone(),
+                sha1: release.asset_index.sha1.clone(),
+                size: release.asset_index.size,
+            },
+        ];
+        let library = |kind: FileKind, artifact: &Artifact| ExpectedFile {
+            kind,
+            path: self.library_path(artifact),
+            url: artifact.url.clone(),
+            sha1: artifact.sha1.clone(),
+            size: artifact.size,
+        };
+        for entry in release
+            .libraries
+            .iter()
+            .filter(|library| library.applies_to(platform))
+        {
+            if let Some(artifact) = &entry.downloads.artifact {
+                files.push(library(FileKind::Library, artifact));
+            }
+            if let Some(native) = entry.native_artifact(platform) {
+                files.push(library(FileKind::Native, native));
+            }
+        }
+        files
+    }
+    /// The objects of the asset index. Each object is listed once. On the resource base of the client
+    pub fn asset_files(
+        &self,
+        client: &APIClient,
+        index: &AssetFile,
+    ) -> Result<Vec<ExpectedFile>, url::ParseError> {
+        let mut seen = HashSet::new();
+        index
+            .objects
+            .values()
+            .filter(|object| seen.insert(&object.hash))
+            .map(|object| {
+                let url = client.game_files.create_resource_url(format!(
+                    "{}/{}",
+                    content_hash(&object.hash),
+                    object.hash
+                ))?;
+                Ok(ExpectedFile {
+                    kind: FileKind::Asset,
+                    path: object_path(&self.assets_directory(), &object.hash),
+                    url: url.into(),
+                    sha1: object.hash.clone(),
+                    size: object.size as u64,
+                })
+            })
+            .collect()
+    }
+    /// Checks the size and SHA-1 of every file the release requires on the platform.
+    ///
+    /// The asset objects are only checked if the asset index is intact. Their URLs are on the resource base of the client
+    pub async fn verify(
+        &self,
+        client: &APIClient,
+        release: &ReleaseData,
+        platform: &Platform,
+    ) -> Result<VerificationReport, Error> {
+        let mut report = VerificationReport {
+            version: release.id.clone(),
+            ..Default::default()
+        };
+        let files = self.expected_files(release, platform);
+        let expected: HashSet<PathBuf> = files.iter().map(|file| file.path.clone()).collect();
+        for (file, state) in check_files(files, true).await? {
+            report.record(file, state);
+        }
+
+        let index_path = self.asset_index_path(&release.asset_index.id);
+        if report.failed().all(|file| file.path != index_path) {
+            let store = self.asset_store(release).await?;
+            let objects = self.asset_files(client, store.index())?;
+            for (file, state) in check_files(objects, true).await? {
+                report.record(file, state);
+            }
+        }
+
+        let version_directory = self.versions_directory().join(&release.id);
+        let natives = self.natives_directory(&release.id);
+        report.extra = walk(&version_directory)
+            .await?
+            .into_iter()
+            .map(|(path, _)| path)
+            .filter(|path| !expected.contains(path) && !path.starts_with(&natives))
+            .filter(|path| *path != self.release_path(&release.id))
+            .collect();
+        Ok(report)
+    }
+    /// Downloads the missing and corrupt files of the report. Returns the files repaired.
+    ///
+    /// Assets are only verified if the asset index was intact. Verify again after the asset index is repaired
+    pub async fn repair(
+        &self,
+        client: &APIClient,
+        report: &VerificationReport,
+    ) -> Result<Vec<ExpectedFile>, Error> {
+        download_files(client, report.failed().cloned()).await
+    }
+}
+
+/// Downloads the files. Each file is verified after it is downloaded. Returns the files downloaded
+pub(crate) async fn download_files(
+    client: &APIClient,
+    files: impl Iterator<Item = ExpectedFile>,
+) -> Result<Vec<ExpectedFile>, Error> {
+    let mut downloaded = Vec::new();
+    let mut downloads = JoinSet::new();
+    for file in files {
+        if downloads.len() >= CONCURRENCY {
+            if let Some(file) = downloads.join_next().await {
+                downloaded.push(file.map_err(|err| Error::Custom(err.to_string()))??);
+            }
+        }
+        downloads.spawn(download_file(client.clone(), file));
+    }
+    while let Some(file) = downloads.join_next().await {
+        downloaded.push(file.map_err(|err| Error::Custom(err.to_string()))??);
+    }
+    Ok(downloaded)
+}
+/// Checks the size of the files. And the SHA-1 if `hashes`
+pub(crate) async fn check_files(
+    files: Vec<ExpectedFile>,
+    hashes: bool,
+) -> Result<Vec<(ExpectedFile, FileState)>, Error> {
+    let mut checked = Vec::with_capacity(files.len());
+    let mut checks = JoinSet::new();
+    for file in files {
+        if checks.len() >= CONCURRENCY {
+            if let Some(check) = checks.join_next().await {
+                checked.push(check.map_err(|err| Error::Custom(err.to_string()))??);
+            }
+        }
+        checks.spawn(async move {
+            let sha1 = hashes.then_some(file.sha1.as_str());
+            let state = check_file(&file.path, Some(file.size), sha1).await?;
+            Ok::<_, Error>((file, state))
+        });
+    }
+    while let Some(check) = checks.join_next().await {
+        checked.push(check.map_err(|err| Error::Custom(err.to_string()))??);
+    }
+    Ok(checked)
+}
+async fn download_file(client: APIClient, file: ExpectedFile) -> Result<ExpectedFile, Error> {
+    let download = Download {
+        urls: client.game_files.urls_for(&file.url)?,
+        file_size: file.size as usize,
+        sha1: Some(file.sha1.clone()),
+        rate_limiter: None,
+        client,
+    };
+    let url = download.download(file.path.clone()).await?;
+    debug!(%url, path = ?file.path, "Downloaded");
+    let actual = sha1_file(&file.path).await?;
+    if !actual.eq_ignore_ascii_case(&file.sha1) {
+        remove_file(&file.path).await?;
+        return Err(HashMismatch {
+            location: url.into(),
+            expected: file.sha1,
+            actual,
+        }
+        .into());
+    }
+    Ok(file)
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        game_files::{
+            assets::store::object_path,
+            installation::{verify::FileKind, Installation},
+            release::{data::ReleaseData, platform::Platform},
+        },
+        test::write,
+        utils::hash::sha1_hex,
+    };
+
+    fn artifact(server: &crate::test::TestServer, path: &str, body: &[u8]) -> serde_json::Value {
+        server.insert(&format!("/libraries/{path}"), body);
+        json!({
+            "path": path,
+            "sha1": sha1_hex(body),
+            "size": body.len(),
+            "url": server.url(&format!("/libraries/{path}"))
+        })
+    }
+
+    #[tokio::test]
+    async fn verify_and_repair() -> anyhow::Result<()> {
+        crate::test::setup();
+        let server = crate::test::TestServer::start().await?;
+        let dir = tempfile::tempdir()?;
+        let installation = Installation::new(dir.path());
+        let index = serde_json::to_vec(&json!({
+            "objects": {
+                "icons/icon_16x16.png": { "hash": sha1_hex(b"icon"), "size": 4 },
+                "icons/icon_32x32.png": { "hash": sha1_hex(b"large icon"), "size": 10 }
+            }
+        }))?;
+        server.insert("/index.json", index.clone());
+        server.insert("/client.jar", b"client".to_vec());
+        for body in [b"icon".as_slice(), b"large icon"] {
+            let hash = sha1_hex(body);
+            server.insert(&format!("/resources/{}/{hash}", &hash[..2]), body);
+        }
+        let mut release = crate::test::release_json("1.12.2");
+        release["downloads"]["client"] = json!({
+            "sha1": sha1_hex(b"client"),
+            "size": 6,
+            "url": server.url("/client.jar")
+        });
+        release["assetIndex"] = json!({
+            "id": "1.12",
+            "sha1": sha1_hex(&index),
+            "size": index.len(),
+            "url": server.url("/index.json")
+        });
+        release["libraries"] = json!([
+            {
+                "name": "com.mojang:realms:1.10.22",
+                "downloads": { "artifact": artifact(&server, "realms.jar", b"realms") }
+            },
+            {
+                "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209",
+                "downloads": {
+                    "classifiers": { "natives-linux": artifact(&server, "natives-linux.jar", b"natives") }
+                },
+                "natives": { "linux": "natives-linux" }
+            },
+            {
+                "name": "ca.weblite:java-objc-bridge:1.0.0",
+                "downloads": { "artifact": artifact(&server, "objc.jar", b"objc") },
+                "rules": [{ "action": "allow", "os": { "name": "osx" } }]
+            }
+        ]);
+        let release: ReleaseData = serde_json::from_value(release)?;
+
+        write(&installation.client_jar_path("1.12.2"), b"client");
+        write(&installation.asset_index_path("1.12"), &index);
+        write(
+            &installation.libraries_directory().join("natives-linux.jar"),
+            b"corrupt",
+        );
+        let assets = installation.assets_directory();
+        write(&object_path(&assets, &sha1_hex(b"icon")), b"icon");
+        write(
+            &object_path(&assets, &sha1_hex(b"large icon")),
+            b"small icon",
+        );
+        write(
+            &installation.versions_directory().join("1.12.2/options.txt"),
+            b"",
+        );
+
+        let linux = Platform::new("linux", "x86_64");
+        let client = server.client();
+        let report = installation.verify(&client, &release, &linux).await?;
+        assert_eq!(report.checked, 6);
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].kind, FileKind::Library);
+        let mut corrupt: Vec<FileKind> = report.corrupt.iter().map(|file| file.file.kind).collect();
+        corrupt.sort_by_key(|kind| *kind as u8);
+        assert_eq!(corrupt, vec![FileKind::Native, FileKind::Asset]);
+        let hash = sha1_hex(b"large icon");
+        assert_eq!(
+            report.corrupt[1].file.url,
+            server.url(&format!("/resources/{}/{hash}", &hash[..2]))
+        );
+        assert_eq!(
+            report.extra,
+            vec![installation.versions_directory().join("1.12.2/options.txt")]
+        );
+
+        assert_eq!(installation.repair(&client, &report).await?.len(), 3);
+        assert!(installation
+            .verify(&client, &release, &linux)
+            .await?
+            .is_ok());
+        Ok(())
+    }
+}
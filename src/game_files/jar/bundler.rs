@@ -0,0 +1,315 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Read, Seek},
+    path::{Component, Path, PathBuf},
+};
+
+use tracing::debug;
+use zip::{result::ZipError, ZipArchive};
+
+use crate::game_files::jar::{copy_with_sha256, JarError};
+
+pub static VERSIONS_LIST: &str = "META-INF/versions.list";
+pub static LIBRARIES_LIST: &str = "META-INF/libraries.list";
+pub static MAIN_CLASS: &str = "META-INF/main-class";
+
+/// A file embedded in the bundler. Described by a line of `versions.list` or `libraries.list`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundledFile {
+    /// The SHA-256 of the embedded jar
+    pub sha256: String,
+    /// The version id for versions. The maven coordinate for libraries
+    pub id: String,
+    /// The path of the jar relative to `META-INF/versions` or `META-INF/libraries`
+    pub path: String,
+}
+
+/// The server jar found at [Downloads::server](crate::game_files::release::Downloads::server) since 1.18.
+///
+/// The jar is a bundler that contains the real server jar and all of its libraries.
+/// ```no_run
+/// use minecraft_rs::game_files::jar::ServerBundler;
+///
+/// let mut bundler = ServerBundler::open("server.jar").unwrap();
+/// let server = bundler.extract("server").unwrap();
+/// println!("java -cp {:?} {}", server.class_path().unwrap(), server.main_class);
+/// ```
+#[derive(Debug)]
+pub struct ServerBundler<R> {
+    archive: ZipArchive<R>,
+    /// The contents of `META-INF/versions.list`
+    pub versions: Vec<BundledFile>,
+    /// The contents of `META-INF/libraries.list`
+    pub libraries: Vec<BundledFile>,
+    /// The class the server should be started with
+    pub main_class: String,
+}
+
+/// The result of [ServerBundler::extract]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedServer {
+    /// The path to the real server jar
+    pub server_jar: PathBuf,
+    /// The paths of the extracted libraries. In the order of `libraries.list`
+    pub libraries: Vec<PathBuf>,
+    /// The class the server should be started with
+    pub main_class: String,
+}
+
+impl ExtractedServer {
+    /// The class path required to launch the server. The libraries followed by the server jar
+    pub fn class_path(&self) -> Result<OsString, std::env::JoinPathsError> {
+        std::env::join_paths(self.libraries.iter().chain(Some(&self.server_jar)))
+    }
+}
+
+impl ServerBundler<File> {
+    /// Opens a bundler from the file system
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, JarError> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> ServerBundler<R> {
+    /// Reads the bundler metadata.
+    ///
+    /// Returns [JarError::NotABundler] for server jars released before 1.18
+    pub fn new(reader: R) -> Result<Self, JarError> {
+        let mut archive = ZipArchive::new(reader)?;
+        let versions = match read_entry(&mut archive, VERSIONS_LIST) {
+            Ok(content) => parse_list(VERSIONS_LIST, &content)?,
+            Err(JarError::MissingEntry(_)) => return Err(JarError::NotABundler),
+            Err(err) => return Err(err),
+        };
+        let libraries = parse_list(LIBRARIES_LIST, &read_entry(&mut archive, LIBRARIES_LIST)?)?;
+        let main_class = read_entry(&mut archive, MAIN_CLASS)?.trim().to_owned();
+        Ok(Self {
+            archive,
+            versions,
+            libraries,
+            main_class,
+        })
+    }
+
+    /// The server jar listed in `versions.list`
+    pub fn server(&self) -> Option<&BundledFile> {
+        self.versions.first()
+    }
+
+    /// Extracts the server jar into `{output}/versions` and the libraries into `{output}/libraries`.
+    ///
+    /// Files that already exist with the expected SHA-256 are not extracted again.
+    pub fn extract(&mut self, output: impl AsRef<Path>) -> Result<ExtractedServer, JarError> {
+        let output = output.as_ref();
+        let server = self
+            .server()
+            .cloned()
+            .ok_or_else(|| JarError::MissingEntry(format!("{VERSIONS_LIST} entry")))?;
+        let server_jar = self.extract_file("versions", &server, output)?;
+        let mut libraries = Vec::with_capacity(self.libraries.len());
+        for library in self.libraries.clone() {
+            libraries.push(self.extract_file("libraries", &library, output)?);
+        }
+        Ok(ExtractedServer {
+            server_jar,
+            libraries,
+            main_class: self.main_class.clone(),
+        })
+    }
+
+    fn extract_file(
+        &mut self,
+        directory: &str,
+        file: &BundledFile,
+        output: &Path,
+    ) -> Result<PathBuf, JarError> {
+        // The path comes from the jar. Only plain names may be joined onto the output
+        let relative = Path::new(&file.path);
+        if relative.as_os_str().is_empty()
+            || !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(JarError::UnsafePath(file.path.clone()));
+        }
+        let location = output.join(directory).join(relative);
+        if location.exists() {
+            let actual = copy_with_sha256(&mut File::open(&location)?, &mut io::sink())?;
+            if actual.eq_ignore_ascii_case(&file.sha256) {
+                debug!(?location, "Bundled file already extracted");
+                return Ok(location);
+            }
+        }
+        if let Some(parent) = location.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let entry_name = format!("META-INF/{directory}/{}", file.path);
+        let mut entry = match self.archive.by_name(&entry_name) {
+            Ok(entry) => entry,
+            Err(ZipError::FileNotFound) => return Err(JarError::MissingEntry(entry_name)),
+            Err(err) => return Err(err.into()),
+        };
+        let actual = copy_with_sha256(&mut entry, &mut File::create(&location)?)?;
+        if !actual.eq_ignore_ascii_case(&file.sha256) {
+            fs::remove_file(&location)?;
+            return Err(JarError::HashMismatch {
+                path: entry_name,
+                expected: file.sha256.clone(),
+                actual,
+            });
+        }
+        Ok(location)
+    }
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<String, JarError> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Err(JarError::MissingEntry(name.to_owned())),
+        Err(err) => return Err(err.into()),
+    };
+    let mut content = String::new();
+    entry.read_to_string(&mut content)?;
+    Ok(content)
+}
+/// Parses the tab separated `{sha256}\t{id}\t{path}` lines
+fn parse_list(file: &'static str, content: &str) -> Result<Vec<BundledFile>, JarError> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut parts = line.split('\t');
+            match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                (Some(sha256), Some(id), Some(path), None) => Ok(BundledFile {
+                    sha256: sha256.to_owned(),
+                    id: id.to_owned(),
+                    path: path.to_owned(),
+                }),
+                _ => Err(JarError::InvalidListing {
+                    file,
+                    line: line.to_owned(),
+                }),
+            }
+        })
+        .collect()
+}
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::Write};
+
+    use sha2::{Digest, Sha256};
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use crate::game_files::jar::{JarError, ServerBundler};
+
+    fn sha256(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    fn create_bundler(path: &std::path::Path, library_hash: Option<&str>) -> anyhow::Result<()> {
+        let server = b"server jar";
+        let library = b"library jar";
+        let mut zip = ZipWriter::new(File::create(path)?);
+        let options = SimpleFileOptions::default();
+        zip.start_file("META-INF/versions.list", options)?;
+        writeln!(zip, "{}\t1.20.4\t1.20.4/server-1.20.4.jar", sha256(server))?;
+        zip.start_file("META-INF/libraries.list", options)?;
+        writeln!(
+            zip,
+            "{}\tcom.mojang:brigadier:1.2.9\tcom/mojang/brigadier/1.2.9/brigadier-1.2.9.jar",
+            library_hash
+                .map(str::to_owned)
+                .unwrap_or_else(|| sha256(library))
+        )?;
+        zip.start_file("META-INF/main-class", options)?;
+        writeln!(zip, "net.minecraft.server.Main")?;
+        zip.start_file("META-INF/versions/1.20.4/server-1.20.4.jar", options)?;
+        zip.write_all(server)?;
+        zip.start_file(
+            "META-INF/libraries/com/mojang/brigadier/1.2.9/brigadier-1.2.9.jar",
+            options,
+        )?;
+        zip.write_all(library)?;
+        zip.finish()?;
+        Ok(())
+    }
+
+    #[test]
+    fn extract_bundler() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let jar = dir.path().join("server.jar");
+        create_bundler(&jar, None)?;
+
+        let mut bundler = ServerBundler::open(&jar)?;
+        assert_eq!(bundler.main_class, "net.minecraft.server.Main");
+        assert_eq!(bundler.server().map(|v| v.id.as_str()), Some("1.20.4"));
+        assert_eq!(bundler.libraries[0].id, "com.mojang:brigadier:1.2.9");
+
+        let output = dir.path().join("server");
+        let extracted = bundler.extract(&output)?;
+        assert_eq!(
+            extracted.server_jar,
+            output.join("versions/1.20.4/server-1.20.4.jar")
+        );
+        assert_eq!(std::fs::read(&extracted.server_jar)?, b"server jar");
+        assert_eq!(std::fs::read(&extracted.libraries[0])?, b"library jar");
+        // A second extraction reuses the verified files
+        assert_eq!(bundler.extract(&output)?, extracted);
+        Ok(())
+    }
+
+    #[test]
+    fn extract_bundler_hash_mismatch() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let jar = dir.path().join("server.jar");
+        create_bundler(&jar, Some(&sha256(b"something else")))?;
+
+        let result = ServerBundler::open(&jar)?.extract(dir.path().join("server"));
+        assert!(matches!(result, Err(JarError::HashMismatch { .. })));
+        assert!(!dir
+            .path()
+            .join("server/libraries/com/mojang/brigadier/1.2.9/brigadier-1.2.9.jar")
+            .exists());
+        Ok(())
+    }
+
+    #[test]
+    fn unsafe_path() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let jar = dir.path().join("server.jar");
+        let mut zip = ZipWriter::new(File::create(&jar)?);
+        let options = SimpleFileOptions::default();
+        zip.start_file("META-INF/versions.list", options)?;
+        writeln!(zip, "{}\t1.20.4\t../../escaped.jar", sha256(b"server jar"))?;
+        zip.start_file("META-INF/libraries.list", options)?;
+        zip.start_file("META-INF/main-class", options)?;
+        writeln!(zip, "net.minecraft.server.Main")?;
+        zip.start_file("META-INF/escaped.jar", options)?;
+        zip.write_all(b"server jar")?;
+        zip.finish()?;
+
+        let result = ServerBundler::open(&jar)?.extract(dir.path().join("server"));
+        assert!(matches!(result, Err(JarError::UnsafePath(_))));
+        assert!(!dir.path().join("escaped.jar").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn not_a_bundler() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let jar = dir.path().join("server.jar");
+        let mut zip = ZipWriter::new(File::create(&jar)?);
+        zip.start_file(
+            "net/minecraft/server/Main.class",
+            SimpleFileOptions::default(),
+        )?;
+        zip.finish()?;
+
+        assert!(matches!(
+            ServerBundler::open(&jar),
+            Err(JarError::NotABundler)
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+//! Readers for the jars distributed through [Downloads](crate::game_files::release::Downloads)
+pub mod bundler;
+pub mod version_info;
+
+use std::io::{self, Read, Write};
+
+pub use bundler::*;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+pub use version_info::*;
+
+#[derive(Debug, Error)]
+pub enum JarError {
+    #[error("Zip Error {0}")]
+    ZipError(#[from] zip::result::ZipError),
+    #[error("IO Error {0}")]
+    IOError(#[from] io::Error),
+    #[error("Serde Json Parse Error {0}")]
+    JSONError(#[from] serde_json::Error),
+    #[error("The jar is missing the entry {0}")]
+    MissingEntry(String),
+    #[error("The jar is not a server bundler")]
+    NotABundler,
+    #[error("Invalid line in {file}: {line:?}")]
+    InvalidListing { file: &'static str, line: String },
+    #[error("The listed path {0:?} escapes the output directory")]
+    UnsafePath(String),
+    #[error("Hash mismatch for {path}. Expected {expected} got {actual}")]
+    HashMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// Copies everything from `reader` into `writer` returning the hex encoded SHA-256 of the data
+pub(crate) fn copy_with_sha256<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<String, io::Error> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])?;
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
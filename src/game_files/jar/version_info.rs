@@ -0,0 +1,201 @@
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use zip::{result::ZipError, ZipArchive};
+
+use crate::game_files::jar::JarError;
+
+pub static VERSION_JSON: &str = "version.json";
+
+/// The `version.json` embedded in the client and server jars since 1.14
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionInfo {
+    /// The Release Name.
+    pub id: String,
+    pub name: String,
+    /// The data version stored inside of worlds
+    pub world_version: u32,
+    /// The protocol version used when connecting to servers
+    pub protocol_version: u32,
+    pub pack_version: PackVersion,
+    pub build_time: DateTime<Utc>,
+    /// The java runtime component. Not present before 1.17
+    pub java_component: Option<String>,
+    /// The major java version. Not present before 1.17
+    pub java_version: Option<u32>,
+    pub series_id: Option<String>,
+    pub release_target: Option<String>,
+    pub stable: bool,
+}
+
+/// The resource and data pack formats of a version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "RawPackVersion")]
+pub struct PackVersion {
+    /// The resource pack format
+    pub resource: u32,
+    /// The data pack format
+    pub data: u32,
+    /// The minor resource pack format. Only present since 1.21.5
+    pub resource_minor: Option<u32>,
+    /// The minor data pack format. Only present since 1.21.5
+    pub data_minor: Option<u32>,
+}
+
+/// Versions before 1.17 only have a single number that is shared by both pack types.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawPackVersion {
+    Single(u32),
+    Split {
+        #[serde(alias = "resource_major")]
+        resource: u32,
+        #[serde(alias = "data_major")]
+        data: u32,
+        resource_minor: Option<u32>,
+        data_minor: Option<u32>,
+    },
+}
+
+impl From<RawPackVersion> for PackVersion {
+    fn from(value: RawPackVersion) -> Self {
+        match value {
+            RawPackVersion::Single(version) => PackVersion {
+                resource: version,
+                data: version,
+                resource_minor: None,
+                data_minor: None,
+            },
+            RawPackVersion::Split {
+                resource,
+                data,
+                resource_minor,
+                data_minor,
+            } => PackVersion {
+                resource,
+                data,
+                resource_minor,
+                data_minor,
+            },
+        }
+    }
+}
+
+impl VersionInfo {
+    /// Reads the `version.json` from a client or server jar
+    pub fn from_jar(path: impl AsRef<Path>) -> Result<Self, JarError> {
+        Self::from_reader(File::open(path)?)
+    }
+    /// Reads the `version.json` from a client or server jar.
+    ///
+    /// Jars released before 1.14 do not contain a `version.json` and will return [JarError::MissingEntry]
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Self, JarError> {
+        let mut archive = ZipArchive::new(reader)?;
+        let entry = match archive.by_name(VERSION_JSON) {
+            Ok(entry) => entry,
+            Err(ZipError::FileNotFound) => {
+                return Err(JarError::MissingEntry(VERSION_JSON.to_owned()))
+            }
+            Err(err) => return Err(err.into()),
+        };
+        serde_json::from_reader(entry).map_err(JarError::from)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::Write};
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use crate::game_files::jar::{JarError, PackVersion, VersionInfo};
+
+    fn create_jar(path: &std::path::Path, version_json: Option<&str>) -> anyhow::Result<()> {
+        let mut zip = ZipWriter::new(File::create(path)?);
+        if let Some(version_json) = version_json {
+            zip.start_file("version.json", SimpleFileOptions::default())?;
+            zip.write_all(version_json.as_bytes())?;
+        }
+        zip.start_file(
+            "net/minecraft/client/main/Main.class",
+            SimpleFileOptions::default(),
+        )?;
+        zip.finish()?;
+        Ok(())
+    }
+
+    #[test]
+    fn read_version_info() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let jar = dir.path().join("client.jar");
+        create_jar(
+            &jar,
+            Some(
+                r#"{
+                    "id": "1.20.4",
+                    "name": "1.20.4",
+                    "world_version": 3700,
+                    "series_id": "main",
+                    "protocol_version": 765,
+                    "pack_version": { "resource": 22, "data": 26 },
+                    "build_time": "2023-12-07T08:33:34+00:00",
+                    "java_component": "java-runtime-gamma",
+                    "java_version": 17,
+                    "stable": true,
+                    "use_editor": false
+                }"#,
+            ),
+        )?;
+        let info = VersionInfo::from_jar(&jar)?;
+        assert_eq!(info.protocol_version, 765);
+        assert_eq!(info.world_version, 3700);
+        assert_eq!(info.pack_version.resource, 22);
+        assert_eq!(info.pack_version.data, 26);
+        assert_eq!(info.java_component.as_deref(), Some("java-runtime-gamma"));
+        Ok(())
+    }
+
+    #[test]
+    fn read_legacy_version_info() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let jar = dir.path().join("client.jar");
+        create_jar(
+            &jar,
+            Some(
+                r#"{
+                    "id": "1.14",
+                    "name": "1.14",
+                    "release_target": "1.14",
+                    "world_version": 1952,
+                    "protocol_version": 477,
+                    "pack_version": 4,
+                    "build_time": "2019-04-23T14:51:04+00:00",
+                    "stable": true
+                }"#,
+            ),
+        )?;
+        let info = VersionInfo::from_jar(&jar)?;
+        assert_eq!(
+            info.pack_version,
+            PackVersion {
+                resource: 4,
+                data: 4,
+                resource_minor: None,
+                data_minor: None
+            }
+        );
+        assert_eq!(info.java_component, None);
+
+        let old_jar = dir.path().join("old.jar");
+        create_jar(&old_jar, None)?;
+        assert!(matches!(
+            VersionInfo::from_jar(&old_jar),
+            Err(JarError::MissingEntry(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+//! The Java runtimes distributed by Mojang. Selected by [JavaVersion::component](crate::game_files::release::data::JavaVersion::component)
+use std::collections::{BTreeMap, HashMap};
+
+use reqwest::header::ACCEPT;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game_files::{release::platform::Platform, Endpoint},
+    http::IntoResult,
+    utils::hash::verify_sha1,
+    APIClient, Error,
+};
+
+/// The path of the runtime list relative to the launcher meta
+pub static JAVA_RUNTIMES_PATH: &str =
+    "v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json";
+
+/// Runtimes keyed by the runtime platform. Then by the component. Such as `linux` and `java-runtime-gamma`
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JavaRuntimes(pub HashMap<String, HashMap<String, Vec<JavaRuntime>>>);
+impl JavaRuntimes {
+    /// The runtime of the component for the platform. None if Mojang does not provide one
+    pub fn select(&self, platform: &Platform, component: &str) -> Option<&JavaRuntime> {
+        self.0
+            .get(self.runtime_key(platform, component)?)?
+            .get(component)?
+            .first()
+    }
+    /// The key of the runtime [selected](JavaRuntimes::select) for the platform.
+    ///
+    /// ARM platforms fall back to the x86_64 runtime if the component is not built for them. It runs under emulation
+    pub fn runtime_key(&self, platform: &Platform, component: &str) -> Option<&'static str> {
+        let key = platform.java_runtime_key()?;
+        let fallback = match key {
+            "mac-os-arm64" => Some("mac-os"),
+            "windows-arm64" => Some("windows-x64"),
+            _ => None,
+        };
+        [Some(key), fallback].into_iter().flatten().find(|key| {
+            self.0
+                .get(*key)
+                .and_then(|components| components.get(component))
+                .is_some_and(|runtimes| !runtimes.is_empty())
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JavaRuntime {
+    pub manifest: RuntimeDownload,
+    pub version: RuntimeVersion,
+}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeVersion {
+    pub name: String,
+    pub released: String,
+}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeDownload {
+    pub sha1: String,
+    pub size: u64,
+    pub url: String,
+}
+
+/// The files of a runtime keyed by their path within the runtime
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeManifest {
+    pub files: BTreeMap<String, RuntimeFile>,
+}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RuntimeFile {
+    File {
+        downloads: RuntimeFileDownloads,
+        #[serde(default)]
+        executable: bool,
+    },
+    Directory,
+    Link {
+        target: String,
+    },
+}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeFileDownloads {
+    pub raw: RuntimeDownload,
+    pub lzma: Option<RuntimeDownload>,
+}
+
+impl Platform {
+    /// The platform as named by the runtime list. None if Mojang provides no runtimes for it
+    pub fn java_runtime_key(&self) -> Option<&'static str> {
+        let key = match (self.os.as_str(), self.arch.as_str()) {
+            ("linux", "x86_64") => "linux",
+            ("linux", "x86") => "linux-i386",
+            ("windows", "x86_64") => "windows-x64",
+            ("windows", "x86") => "windows-x86",
+            ("windows", "arm64") => "windows-arm64",
+            ("osx", "x86_64") => "mac-os",
+            ("osx", "arm64") => "mac-os-arm64",
+            _ => return None,
+        };
+        Some(key)
+    }
+}
+
+impl APIClient {
+    /// Pulls the list of Java runtimes from the launcher meta
+    pub async fn java_runtimes(&self) -> Result<JavaRuntimes, Error> {
+        let (response, _) = self
+            .get_from_endpoint(Endpoint::LauncherMeta, JAVA_RUNTIMES_PATH)
+            .await?;
+        let bytes = response.into_result::<Error>().await?.bytes().await?;
+        serde_json::from_slice(&bytes).map_err(Error::from)
+    }
+}
+impl JavaRuntime {
+    /// Pulls the file list of the runtime. Verified against the SHA-1 of the manifest
+    pub async fn get_manifest(&self, client: &APIClient) -> Result<RuntimeManifest, Error> {
+        let url = client.game_files.redirect(&self.manifest.url)?;
+        let bytes = client
+            .process_bytes(
+                client
+                    .http_client
+                    .get(url)
+                    .header(ACCEPT, "application/json"),
+            )
+            .await?;
+        verify_sha1(&self.manifest.url, &bytes, &self.manifest.sha1)?;
+        serde_json::from_slice(&bytes).map_err(Error::from)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::game_files::{
+        java_runtime::{JavaRuntimes, RuntimeFile, RuntimeManifest},
+        release::platform::Platform,
+    };
+
+    #[test]
+    fn select_runtime() -> anyhow::Result<()> {
+        let runtime = json!([{
+            "availability": { "group": 1, "progress": 100 },
+            "manifest": { "sha1": "0000000000000000000000000000000000000000", "size": 0, "url": "https://piston-meta.mojang.com/manifest.json" },
+            "version": { "name": "17.0.8", "released": "2023-07-17T16:00:00+00:00" }
+        }]);
+        let runtimes: JavaRuntimes = serde_json::from_value(json!({
+            "linux": { "java-runtime-gamma": runtime, "jre-legacy": [] },
+            "mac-os-arm64": { "java-runtime-gamma": runtime },
+            "mac-os": { "jre-legacy": runtime }
+        }))?;
+        let linux = Platform::new("linux", "x86_64");
+        assert_eq!(
+            runtimes
+                .select(&linux, "java-runtime-gamma")
+                .map(|runtime| runtime.version.name.as_str()),
+            Some("17.0.8")
+        );
+        assert!(runtimes.select(&linux, "jre-legacy").is_none());
+        let mac_arm64 = Platform::new("osx", "arm64");
+        assert!(runtimes.select(&mac_arm64, "java-runtime-gamma").is_some());
+        assert_eq!(
+            runtimes.runtime_key(&mac_arm64, "java-runtime-gamma"),
+            Some("mac-os-arm64")
+        );
+        assert!(runtimes
+            .select(&Platform::new("linux", "arm64"), "java-runtime-gamma")
+            .is_none());
+        assert_eq!(
+            runtimes.runtime_key(&mac_arm64, "jre-legacy"),
+            Some("mac-os")
+        );
+
+        let manifest: RuntimeManifest = serde_json::from_value(json!({
+            "files": {
+                "bin": { "type": "directory" },
+                "bin/java": {
+                    "type": "file",
+                    "executable": true,
+                    "downloads": { "raw": { "sha1": "", "size": 1, "url": "https://piston-data.mojang.com/java" } }
+                },
+                "lib/libjli.so": { "type": "link", "target": "../jli/libjli.so" }
+            }
+        }))?;
+        assert!(matches!(
+            manifest.files["bin/java"],
+            RuntimeFile::File {
+                executable: true,
+                ..
+            }
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+//! Detects changes to the version manifest.
+//!
+//! [VersionManifest::changes_since] compares two manifests. [ManifestWatcher] polls the launcher meta and reports the changes as [ManifestEvent]s
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::{
+    fs::read,
+    sync::mpsc::{channel, Receiver},
+    time::{interval, MissedTickBehavior},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    game_files::version_manifest::{Version, VersionManifest},
+    utils::write_atomic,
+    APIClient, Error,
+};
+
+/// The differences between two version manifests
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ManifestChanges {
+    /// Versions only found in the current manifest. Newest first
+    pub added: Vec<Version>,
+    /// Versions only found in the previous manifest. Newest first
+    pub removed: Vec<Version>,
+    /// Versions where the [sha1](Version::sha1) or [compliance_level](Version::compliance_level) changed
+    pub changed: Vec<ChangedVersion>,
+}
+impl ManifestChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+impl IntoIterator for ManifestChanges {
+    type Item = ManifestEvent;
+    type IntoIter = std::vec::IntoIter<ManifestEvent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let added = self.added.into_iter().map(ManifestEvent::Added);
+        let removed = self.removed.into_iter().map(ManifestEvent::Removed);
+        let changed = self.changed.into_iter().map(ManifestEvent::Changed);
+        added
+            .chain(removed)
+            .chain(changed)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+/// A version found in both manifests with different metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangedVersion {
+    pub previous: Version,
+    pub current: Version,
+}
+impl ChangedVersion {
+    /// The release JSON was republished
+    pub fn sha1_changed(&self) -> bool {
+        !self.previous.sha1.eq_ignore_ascii_case(&self.current.sha1)
+    }
+    pub fn compliance_level_changed(&self) -> bool {
+        self.previous.compliance_level != self.current.compliance_level
+    }
+}
+
+/// A single change to the version manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", content = "version", rename_all = "snake_case")]
+pub enum ManifestEvent {
+    /// A version was published
+    Added(Version),
+    /// A version was pulled from the manifest
+    Removed(Version),
+    Changed(ChangedVersion),
+}
+
+impl VersionManifest {
+    /// Compares the manifest against an older copy. Versions are matched by id
+    pub fn changes_since(&self, previous: &VersionManifest) -> ManifestChanges {
+        let previous_versions: HashMap<&str, &Version> = previous
+            .versions
+            .iter()
+            .map(|version| (version.id.as_str(), version))
+            .collect();
+        let mut changes = ManifestChanges::default();
+        for version in &self.versions {
+            match previous_versions.get(version.id.as_str()) {
+                None => changes.added.push(version.clone()),
+                Some(old) => {
+                    let changed = ChangedVersion {
+                        previous: (*old).clone(),
+                        current: version.clone(),
+                    };
+                    if changed.sha1_changed() || changed.compliance_level_changed() {
+                        changes.changed.push(changed);
+                    }
+                }
+            }
+        }
+        let current_ids: HashSet<&str> = self
+            .versions
+            .iter()
+            .map(|version| version.id.as_str())
+            .collect();
+        changes.removed = previous
+            .versions
+            .iter()
+            .filter(|version| !current_ids.contains(version.id.as_str()))
+            .cloned()
+            .collect();
+        changes
+    }
+}
+
+/// Polls the version manifest and reports the changes.
+///
+/// The last seen manifest is stored in the state file so changes made while the watcher was not running are reported on the next poll.
+/// The first poll without a state file only records the manifest.
+/// ```no_run
+/// # async fn watch(client: minecraft_rs::APIClient) -> Result<(), minecraft_rs::Error> {
+/// use std::time::Duration;
+///
+/// use minecraft_rs::game_files::manifest_watcher::{ManifestEvent, ManifestWatcher};
+///
+/// let watcher = ManifestWatcher::new(client, "manifest_state.json", Duration::from_secs(300)).await?;
+/// let mut events = watcher.spawn(16);
+/// while let Some(event) = events.recv().await {
+///     if let ManifestEvent::Added(version) = event {
+///         println!("{} {} was published", version.release_type, version.id);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ManifestWatcher {
+    client: APIClient,
+    state_file: PathBuf,
+    poll_interval: Duration,
+    last_seen: Option<VersionManifest>,
+}
+impl ManifestWatcher {
+    /// Creates a watcher. Loads the last seen manifest from the state file if it exists
+    pub async fn new(
+        client: APIClient,
+        state_file: impl Into<PathBuf>,
+        poll_interval: Duration,
+    ) -> Result<Self, Error> {
+        let state_file = state_file.into();
+        let last_seen = match read(&state_file).await {
+            Ok(bytes) => Some(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            client,
+            state_file,
+            poll_interval,
+            last_seen,
+        })
+    }
+    /// The manifest seen by the last poll
+    pub fn last_seen(&self) -> Option<&VersionManifest> {
+        self.last_seen.as_ref()
+    }
+    /// Pulls the manifest once and stores it in the state file
+    pub async fn poll(&mut self) -> Result<ManifestChanges, Error> {
+        let manifest = self.client.version_manifest().await?;
+        let changes = match &self.last_seen {
+            Some(last_seen) => manifest.changes_since(last_seen),
+            None => ManifestChanges::default(),
+        };
+        if self.last_seen.is_none() || !changes.is_empty() {
+            write_atomic(&self.state_file, &serde_json::to_vec(&manifest)?).await?;
+        }
+        self.last_seen = Some(manifest);
+        Ok(changes)
+    }
+    /// Polls at the interval on a new task. Failed polls are logged and retried on the next tick.
+    ///
+    /// The task stops once the receiver is dropped
+    pub fn spawn(mut self, buffer: usize) -> Receiver<ManifestEvent> {
+        let (sender, receiver) = channel(buffer);
+        tokio::spawn(async move {
+            let mut ticks = interval(self.poll_interval);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                if sender.is_closed() {
+                    return;
+                }
+                let changes = match self.poll().await {
+                    Ok(changes) => changes,
+                    Err(err) => {
+                        warn!(?err, "Could not poll the version manifest");
+                        continue;
+                    }
+                };
+                for event in changes {
+                    debug!(?event, "Version manifest changed");
+                    if sender.send(event).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        receiver
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::game_files::{
+        manifest_watcher::{ManifestEvent, ManifestWatcher},
+        version_manifest::VersionManifest,
+    };
+
+    #[test]
+    fn changes_since() -> anyhow::Result<()> {
+        let previous = crate::test::version_manifest();
+        let mut current = crate::test::version_manifest_json();
+        let versions = current["versions"].as_array_mut().unwrap();
+        versions.retain(|version| version["id"] != "24w14potato");
+        versions.insert(0, versions[0].clone());
+        versions[0]["id"] = "24w18a".into();
+        versions[1]["sha1"] = "1111111111111111111111111111111111111111".into();
+        let current: VersionManifest = serde_json::from_value(current)?;
+
+        let changes = current.changes_since(&previous);
+        assert_eq!(changes.added.len(), 1);
+        assert_eq!(changes.added[0].id, "24w18a");
+        assert_eq!(changes.removed.len(), 1);
+        assert_eq!(changes.removed[0].id, "24w14potato");
+        assert_eq!(changes.changed.len(), 1);
+        assert!(changes.changed[0].sha1_changed());
+        assert!(!changes.changed[0].compliance_level_changed());
+        assert!(previous.changes_since(&previous).is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watcher() -> anyhow::Result<()> {
+        crate::test::setup();
+        let server = crate::test::TestServer::start().await?;
+        let manifest_path = "/launcher_meta/mc/game/version_manifest_v2.json";
+        let mut manifest = crate::test::version_manifest_json();
+        server.insert(manifest_path, serde_json::to_vec(&manifest)?);
+        let client = server.client();
+        let dir = tempfile::tempdir()?;
+        let state_file = dir.path().join("manifest.json");
+
+        let mut watcher =
+            ManifestWatcher::new(client.clone(), &state_file, Duration::from_millis(10)).await?;
+        assert!(watcher.poll().await?.is_empty());
+        assert!(state_file.exists());
+
+        // Published while the watcher was not running
+        let versions = manifest["versions"].as_array_mut().unwrap();
+        versions.insert(0, versions[0].clone());
+        versions[0]["id"] = "1.20.6".into();
+        server.insert(manifest_path, serde_json::to_vec(&manifest)?);
+
+        let watcher = ManifestWatcher::new(client, &state_file, Duration::from_millis(10)).await?;
+        assert_eq!(
+            watcher.last_seen().map(|last| last.versions.len()),
+            Some(11)
+        );
+        let mut events = watcher.spawn(4);
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await?;
+        assert!(matches!(event, Some(ManifestEvent::Added(version)) if version.id == "1.20.6"));
+        Ok(())
+    }
+}
@@ -0,0 +1,519 @@
+//! Copies game files into a directory that can be served as a mirror for machines without internet access.
+//!
+//! The directory contains one folder per [Endpoint] matching the layout of the URL bases.
+//! Point [GameFilesAPIBuilder::from_mirror] at wherever the directory is served from.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use reqwest::Url;
+use serde_json::Value;
+use tokio::{
+    fs::{metadata, remove_file, rename},
+    task::JoinSet,
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    error::HashMismatch,
+    game_files::{
+        assets::{content_hash, data::AssetFile},
+        release::data::ReleaseData,
+        version_manifest::VersionManifest,
+        Endpoint, GameFilesAPIBuilder, VERSION_MANIFEST_PATH,
+    },
+    http::IntoResult,
+    utils::{
+        download_from_mirrors_limited,
+        hash::{sha1_file, sha1_hex},
+        temporary_path, write_atomic,
+    },
+    APIClient, Error,
+};
+#[cfg(feature = "mirror-server")]
+pub mod server;
+
+/// The directory within the mirror holding the files of the endpoint
+pub fn directory_name(endpoint: Endpoint) -> &'static str {
+    match endpoint {
+        Endpoint::LauncherMeta => "launcher_meta",
+        Endpoint::Resource => "resources",
+        Endpoint::Library => "libraries",
+    }
+}
+
+/// Creates a mirror of the selected versions.
+///
+/// The version manifest keeps every version. Only the selected versions point at the mirror.
+/// ```no_run
+/// # async fn mirror(client: minecraft_rs::APIClient) -> Result<(), minecraft_rs::Error> {
+/// use minecraft_rs::game_files::mirror::MirrorBuilder;
+///
+/// let report = MirrorBuilder::new("mirror", "http://mirror.local/minecraft")
+///     .version("1.20.4")
+///     .server(false)
+///     .build(&client)
+///     .await?;
+/// println!("Downloaded {} of {} files", report.downloaded, report.files);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MirrorBuilder {
+    /// The directory the mirror is written to
+    pub directory: PathBuf,
+    /// The URL the directory will be served from
+    pub mirror_base: String,
+    /// The ids of the versions to mirror
+    pub versions: Vec<String>,
+    /// Mirror the asset objects. The asset index is always mirrored
+    pub assets: bool,
+    pub libraries: bool,
+    /// Mirror the server jar and server mappings
+    pub server: bool,
+    /// The number of files downloaded at once
+    pub concurrency: usize,
+}
+
+/// The result of [MirrorBuilder::build]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MirrorReport {
+    pub versions: Vec<String>,
+    /// Files in the mirror. Not counting the version manifest and release JSONs
+    pub files: usize,
+    /// Files that were not already in the mirror
+    pub downloaded: usize,
+    pub downloaded_bytes: u64,
+}
+
+/// A file referenced by the mirrored JSONs
+#[derive(Debug, Clone)]
+struct MirrorFile {
+    /// In the order they are tried
+    urls: Vec<Url>,
+    sha1: Option<String>,
+}
+
+impl MirrorBuilder {
+    pub fn new(directory: impl Into<PathBuf>, mirror_base: impl Into<String>) -> Self {
+        Self {
+            directory: directory.into(),
+            mirror_base: mirror_base.into().trim_end_matches('/').to_owned(),
+            versions: Vec::new(),
+            assets: true,
+            libraries: true,
+            server: true,
+            concurrency: 8,
+        }
+    }
+    /// Adds a version to mirror
+    pub fn version(mut self, id: impl Into<String>) -> Self {
+        self.versions.push(id.into());
+        self
+    }
+    pub fn assets(mut self, assets: bool) -> Self {
+        self.assets = assets;
+        self
+    }
+    pub fn libraries(mut self, libraries: bool) -> Self {
+        self.libraries = libraries;
+        self
+    }
+    pub fn server(mut self, server: bool) -> Self {
+        self.server = server;
+        self
+    }
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+    /// The [GameFilesAPIBuilder] for machines using the mirror
+    pub fn game_files(&self) -> GameFilesAPIBuilder {
+        GameFilesAPIBuilder::from_mirror(&self.mirror_base)
+    }
+
+    /// Downloads the selected versions into the mirror. Files already in the mirror are skipped if their SHA-1 matches.
+    ///
+    /// The version manifest is written last. A failed build never advertises incomplete versions
+    pub async fn build(&self, client: &APIClient) -> Result<MirrorReport, Error> {
+        let (response, _) = client
+            .get_from_endpoint(Endpoint::LauncherMeta, VERSION_MANIFEST_PATH)
+            .await?;
+        let manifest_bytes = response.into_result::<Error>().await?.bytes().await?;
+        let manifest: VersionManifest = serde_json::from_slice(&manifest_bytes)?;
+        let mut manifest_json: Value = serde_json::from_slice(&manifest_bytes)?;
+
+        let mut files = BTreeMap::new();
+        let mut report = MirrorReport::default();
+        for id in &self.versions {
+            let version = manifest
+                .get_version(id)
+                .ok_or_else(|| Error::UnknownVersion(id.clone()))?;
+            let release_bytes = version.get_release_bytes(client).await?;
+            let release: ReleaseData = serde_json::from_slice(&release_bytes)?;
+            let mut release_json: Value = serde_json::from_slice(&release_bytes)?;
+
+            if let Some(downloads) = release_json
+                .get_mut("downloads")
+                .and_then(Value::as_object_mut)
+            {
+                for (name, download) in downloads.iter_mut() {
+                    if self.server || !name.starts_with("server") {
+                        self.rewrite_urls(client, download, &mut files);
+                    }
+                }
+            }
+            if self.libraries {
+                if let Some(libraries) = release_json.get_mut("libraries") {
+                    self.rewrite_urls(client, libraries, &mut files);
+                }
+            }
+            if let Some(logging) = release_json.get_mut("logging") {
+                self.rewrite_urls(client, logging, &mut files);
+            }
+            if let Some(asset_index) = release_json.get_mut("assetIndex") {
+                let mut index_files = BTreeMap::new();
+                self.rewrite_urls(client, asset_index, &mut index_files);
+                let asset_file = self
+                    .mirror_asset_index(client, &release, index_files)
+                    .await?;
+                if self.assets {
+                    self.add_asset_objects(client, &asset_file, &mut files)?;
+                }
+            }
+
+            let Some((release_location, release_url)) = self.mirror_location(client, &version.url)
+            else {
+                warn!(url = ?version.url, "Release JSON is not served by a known endpoint");
+                continue;
+            };
+            let release_bytes = serde_json::to_vec(&release_json)?;
+            write_atomic(&release_location, &release_bytes).await?;
+            if let Some(entry) = manifest_json["versions"]
+                .as_array_mut()
+                .and_then(|versions| versions.iter_mut().find(|entry| entry["id"] == **id))
+            {
+                entry["url"] = Value::String(release_url);
+                entry["sha1"] = Value::String(sha1_hex(&release_bytes));
+            }
+            report.versions.push(id.clone());
+        }
+
+        report.files = files.len();
+        let mut downloads = JoinSet::new();
+        for (location, file) in files {
+            if downloads.len() >= self.concurrency {
+                if let Some(downloaded) = downloads.join_next().await {
+                    report.add(downloaded.map_err(|err| Error::Custom(err.to_string()))??);
+                }
+            }
+            downloads.spawn(mirror_file(client.clone(), file, location));
+        }
+        while let Some(downloaded) = downloads.join_next().await {
+            report.add(downloaded.map_err(|err| Error::Custom(err.to_string()))??);
+        }
+
+        let manifest_location = self
+            .directory
+            .join(directory_name(Endpoint::LauncherMeta))
+            .join(VERSION_MANIFEST_PATH);
+        write_atomic(&manifest_location, &serde_json::to_vec(&manifest_json)?).await?;
+        info!(?report, "Mirror built");
+        Ok(report)
+    }
+
+    /// Where a file is stored in the mirror and the URL it will be served from
+    fn mirror_location(&self, client: &APIClient, url: &str) -> Option<(PathBuf, String)> {
+        let url = Url::parse(url).ok()?;
+        let (endpoint, path) = client.game_files.endpoint_path(&url)?;
+        let directory = directory_name(endpoint);
+        Some((
+            self.directory.join(directory).join(&path),
+            format!("{}/{directory}/{path}", self.mirror_base),
+        ))
+    }
+
+    /// Rewrites every `url` within the JSON to the mirror and records the file
+    fn rewrite_urls(
+        &self,
+        client: &APIClient,
+        value: &mut Value,
+        files: &mut BTreeMap<PathBuf, MirrorFile>,
+    ) {
+        match value {
+            Value::Array(values) => {
+                for value in values {
+                    self.rewrite_urls(client, value, files);
+                }
+            }
+            Value::Object(object) => {
+                if let Some(Value::String(url)) = object.get("url") {
+                    match (Url::parse(url), self.mirror_location(client, url)) {
+                        (Ok(source), Some((location, mirror_url))) => {
+                            let sha1 = object.get("sha1").and_then(Value::as_str);
+                            files.insert(
+                                location,
+                                MirrorFile {
+                                    urls: vec![source],
+                                    sha1: sha1.map(str::to_owned),
+                                },
+                            );
+                            object.insert("url".to_owned(), Value::String(mirror_url));
+                        }
+                        _ if url.is_empty() => {}
+                        _ => warn!(?url, "URL is not served by a known endpoint. Not mirroring"),
+                    }
+                }
+                for (key, value) in object.iter_mut() {
+                    if key != "url" {
+                        self.rewrite_urls(client, value, files);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn mirror_asset_index(
+        &self,
+        client: &APIClient,
+        release: &ReleaseData,
+        index_files: BTreeMap<PathBuf, MirrorFile>,
+    ) -> Result<AssetFile, Error> {
+        let Some(location) = index_files.into_keys().next() else {
+            return release.asset_index.get_asset_file(client).await;
+        };
+        let bytes = match tokio::fs::read(&location).await {
+            Ok(bytes) if sha1_hex(&bytes).eq_ignore_ascii_case(&release.asset_index.sha1) => bytes,
+            _ => {
+                let bytes = release.asset_index.get_asset_file_bytes(client).await?;
+                write_atomic(&location, &bytes).await?;
+                bytes
+            }
+        };
+        serde_json::from_slice(&bytes).map_err(Error::from)
+    }
+
+    fn add_asset_objects(
+        &self,
+        client: &APIClient,
+        asset_file: &AssetFile,
+        files: &mut BTreeMap<PathBuf, MirrorFile>,
+    ) -> Result<(), url::ParseError> {
+        let directory = self.directory.join(directory_name(Endpoint::Resource));
+        for object in asset_file.objects.values() {
+            let path = format!("{}/{}", content_hash(&object.hash), object.hash);
+            files.insert(
+                directory.join(&path),
+                MirrorFile {
+                    urls: client.game_files.create_urls(Endpoint::Resource, &path)?,
+                    sha1: Some(object.hash.clone()),
+                },
+            );
+        }
+        Ok(())
+    }
+}
+impl MirrorReport {
+    fn add(&mut self, downloaded: Option<u64>) {
+        if let Some(bytes) = downloaded {
+            self.downloaded += 1;
+            self.downloaded_bytes += bytes;
+        }
+    }
+}
+
+/// Downloads the file unless the mirror already has it. Returns the number of bytes downloaded
+async fn mirror_file(
+    client: APIClient,
+    file: MirrorFile,
+    location: PathBuf,
+) -> Result<Option<u64>, Error> {
+    if is_mirrored(&location, file.sha1.as_deref()).await {
+        return Ok(None);
+    }
+    // Streamed into a temporary file so jars are never held in memory and a failed download leaves nothing behind
+    let temporary = temporary_path(&location);
+    let url = match download_from_mirrors_limited(
+        &file.urls,
+        &client.http_client,
+        temporary.clone(),
+        &[],
+        |_| {},
+    )
+    .await
+    {
+        Ok(url) => url,
+        Err(err) => {
+            let _ = remove_file(&temporary).await;
+            return Err(err);
+        }
+    };
+    debug!(%url, ?location, "Mirroring");
+    if let Some(sha1) = &file.sha1 {
+        let actual = sha1_file(&temporary).await?;
+        if !actual.eq_ignore_ascii_case(sha1) {
+            remove_file(&temporary).await?;
+            return Err(HashMismatch {
+                location: url.into(),
+                expected: sha1.clone(),
+                actual,
+            }
+            .into());
+        }
+    }
+    let size = metadata(&temporary).await?.len();
+    rename(&temporary, &location).await?;
+    Ok(Some(size))
+}
+async fn is_mirrored(location: &Path, sha1: Option<&str>) -> bool {
+    match sha1 {
+        Some(sha1) => sha1_file(location)
+            .await
+            .is_ok_and(|actual| actual.eq_ignore_ascii_case(sha1)),
+        None => location.exists(),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        game_files::{
+            mirror::{mirror_file, MirrorBuilder, MirrorFile},
+            release::data::ReleaseData,
+            version_manifest::VersionManifest,
+        },
+        utils::hash::sha1_hex,
+        Error,
+    };
+
+    #[tokio::test]
+    async fn build_mirror() -> anyhow::Result<()> {
+        crate::test::setup();
+        let server = crate::test::TestServer::start().await?;
+        let client = server.client();
+        let file = |path: &str, body: &[u8]| {
+            server.insert(path, body.to_vec());
+            json!({ "sha1": sha1_hex(body), "size": body.len(), "url": server.url(path) })
+        };
+        let asset_hash = sha1_hex(b"asset");
+        file(
+            &format!("/resources/{}/{asset_hash}", &asset_hash[..2]),
+            b"asset",
+        );
+        let asset_index =
+            json!({ "objects": { "icons/icon.png": { "hash": asset_hash, "size": 5 } } });
+        let mut asset_index_download = file(
+            "/launcher_meta/v1/packages/index/1.20.4.json",
+            &serde_json::to_vec(&asset_index)?,
+        );
+        asset_index_download["id"] = "1.20.4".into();
+
+        let mut release = crate::test::release_json("1.20.4");
+        release["downloads"]["client"] =
+            file("/launcher_meta/v1/objects/client/client.jar", b"client");
+        release["downloads"]["server"] =
+            file("/launcher_meta/v1/objects/server/server.jar", b"server");
+        let mut artifact = file("/libraries/com/example/lib/1.0/lib-1.0.jar", b"library");
+        artifact["path"] = "com/example/lib/1.0/lib-1.0.jar".into();
+        release["libraries"] =
+            json!([{ "name": "com.example:lib:1.0", "downloads": { "artifact": artifact } }]);
+        release["assetIndex"] = asset_index_download;
+        let release_download = file(
+            "/launcher_meta/v1/packages/release/1.20.4.json",
+            &serde_json::to_vec(&release)?,
+        );
+        let mut manifest = crate::test::version_manifest_json();
+        let entry = manifest["versions"]
+            .as_array_mut()
+            .unwrap()
+            .iter_mut()
+            .find(|entry| entry["id"] == "1.20.4")
+            .unwrap();
+        entry["url"] = release_download["url"].clone();
+        entry["sha1"] = release_download["sha1"].clone();
+        file(
+            "/launcher_meta/mc/game/version_manifest_v2.json",
+            &serde_json::to_vec(&manifest)?,
+        );
+
+        let dir = tempfile::tempdir()?;
+        let mirror = MirrorBuilder::new(dir.path(), "http://mirror.invalid/minecraft/")
+            .version("1.20.4")
+            .server(false);
+        let report = mirror.build(&client).await?;
+        assert_eq!(report.files, 3);
+        assert_eq!(report.downloaded, 3);
+        assert_eq!(mirror.build(&client).await?.downloaded, 0);
+
+        let meta = dir.path().join("launcher_meta");
+        assert!(meta.join("v1/objects/client/client.jar").exists());
+        assert!(!meta.join("v1/objects/server/server.jar").exists());
+        assert!(meta.join("v1/packages/index/1.20.4.json").exists());
+        assert!(dir
+            .path()
+            .join("libraries/com/example/lib/1.0/lib-1.0.jar")
+            .exists());
+        assert!(dir
+            .path()
+            .join(format!("resources/{}/{asset_hash}", &asset_hash[..2]))
+            .exists());
+
+        let manifest: VersionManifest = serde_json::from_slice(&std::fs::read(
+            meta.join("mc/game/version_manifest_v2.json"),
+        )?)?;
+        let version = manifest.get_version("1.20.4").unwrap();
+        assert_eq!(
+            version.url,
+            "http://mirror.invalid/minecraft/launcher_meta/v1/packages/release/1.20.4.json"
+        );
+        let release_bytes = std::fs::read(meta.join("v1/packages/release/1.20.4.json"))?;
+        assert_eq!(version.sha1, sha1_hex(&release_bytes));
+        let release: ReleaseData = serde_json::from_slice(&release_bytes)?;
+        assert_eq!(
+            release.downloads.client.url,
+            "http://mirror.invalid/minecraft/launcher_meta/v1/objects/client/client.jar"
+        );
+        assert_eq!(
+            release.downloads.server.unwrap().url,
+            server.url("/launcher_meta/v1/objects/server/server.jar")
+        );
+        assert_eq!(
+            mirror.game_files().library_base,
+            "http://mirror.invalid/minecraft/libraries"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mirror_file_streams() -> anyhow::Result<()> {
+        let server = crate::test::TestServer::start().await?;
+        server.insert("/client.jar", b"client".to_vec());
+        let dir = tempfile::tempdir()?;
+        let location = dir.path().join("client.jar");
+        let file = |sha1: &str| MirrorFile {
+            urls: vec![server.url("/client.jar").parse().unwrap()],
+            sha1: Some(sha1.to_owned()),
+        };
+
+        let result =
+            mirror_file(server.client(), file(&sha1_hex(b"other")), location.clone()).await;
+        assert!(matches!(result, Err(Error::HashMismatch(_))));
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 0);
+
+        let size = mirror_file(
+            server.client(),
+            file(&sha1_hex(b"client")),
+            location.clone(),
+        )
+        .await?;
+        assert_eq!(size, Some(6));
+        assert_eq!(std::fs::read(&location)?, b"client");
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 1);
+        let skipped = mirror_file(server.client(), file(&sha1_hex(b"client")), location).await?;
+        assert_eq!(skipped, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,349 @@
+//! A static HTTP server for a mirror directory. Requires the `mirror-server` feature.
+//!
+//! Supports `HEAD`, single byte ranges and `ETag` revalidation. Meant for localhost and tests, not for the open internet.
+use std::{
+    convert::Infallible,
+    io::{self, SeekFrom},
+    net::SocketAddr,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+use futures_util::TryStreamExt;
+use http_body_util::{combinators::BoxBody, BodyExt, Full, StreamBody};
+use hyper::{
+    body::{Bytes, Frame, Incoming},
+    header::{
+        ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_NONE_MATCH, RANGE,
+    },
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use percent_encoding::percent_decode_str;
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+    net::{TcpListener, ToSocketAddrs},
+    task::JoinHandle,
+};
+use tokio_util::io::ReaderStream;
+use tracing::{debug, warn};
+
+use crate::game_files::GameFilesAPIBuilder;
+
+/// Serves a directory created by [MirrorBuilder](super::MirrorBuilder).
+///
+/// The server stops when dropped.
+/// ```no_run
+/// # async fn serve() -> std::io::Result<()> {
+/// use minecraft_rs::{game_files::mirror::server::MirrorServer, APIClient};
+///
+/// let server = MirrorServer::start("mirror").await?;
+/// let client = APIClient::new(reqwest::Client::new(), server.game_files());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MirrorServer {
+    address: SocketAddr,
+    task: JoinHandle<()>,
+}
+impl MirrorServer {
+    /// Serves the directory on a random port of localhost
+    pub async fn start(directory: impl Into<PathBuf>) -> io::Result<Self> {
+        Self::bind(directory, "127.0.0.1:0").await
+    }
+    pub async fn bind(
+        directory: impl Into<PathBuf>,
+        address: impl ToSocketAddrs,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(address).await?;
+        let address = listener.local_addr()?;
+        let directory: Arc<Path> = Arc::from(directory.into());
+        let task = tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(err) => {
+                        warn!(?err, "Could not accept connection");
+                        continue;
+                    }
+                };
+                let directory = directory.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |request| {
+                        let directory = directory.clone();
+                        async move { Ok::<_, Infallible>(respond(&directory, request).await) }
+                    });
+                    if let Err(err) = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await
+                    {
+                        debug!(?err, "Connection closed with an error");
+                    }
+                });
+            }
+        });
+        Ok(Self { address, task })
+    }
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+    /// The URL the mirror is served at. Without a trailing slash
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+    /// A [GameFilesAPIBuilder] using the server for every endpoint
+    pub fn game_files(&self) -> GameFilesAPIBuilder {
+        GameFilesAPIBuilder::from_mirror(&self.base_url())
+    }
+}
+impl Drop for MirrorServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// File bodies are streamed. Nothing but the current chunk is held in memory
+type Body = BoxBody<Bytes, io::Error>;
+
+async fn respond(directory: &Path, request: Request<Incoming>) -> Response<Body> {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return empty(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    let Some(location) = resolve(directory, request.uri().path()) else {
+        return empty(StatusCode::NOT_FOUND);
+    };
+    match serve_file(&location, &request).await {
+        Ok(response) => response,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => empty(StatusCode::NOT_FOUND),
+        Err(err) => {
+            warn!(?err, ?location, "Could not serve file");
+            empty(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn serve_file(location: &Path, request: &Request<Incoming>) -> io::Result<Response<Body>> {
+    let mut file = File::open(location).await?;
+    let metadata = file.metadata().await?;
+    if !metadata.is_file() {
+        return Err(io::ErrorKind::NotFound.into());
+    }
+    let length = metadata.len();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| modified.as_nanos())
+        .unwrap_or_default();
+    let etag = format!("\"{length:x}-{modified:x}\"");
+    let content_type = match location
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("jar") => "application/java-archive",
+        _ => "application/octet-stream",
+    };
+    let response = Response::builder()
+        .header(ETAG, &etag)
+        .header(ACCEPT_RANGES, "bytes")
+        .header(CONTENT_TYPE, content_type);
+
+    let if_none_match = request.headers().get(IF_NONE_MATCH);
+    if if_none_match.is_some_and(|value| value.as_bytes() == etag.as_bytes()) {
+        return Ok(with_body(
+            response.status(StatusCode::NOT_MODIFIED),
+            Bytes::new(),
+        ));
+    }
+    let range = request
+        .headers()
+        .get(RANGE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| parse_range(value, length));
+    let (response, start, end) = match range {
+        None | Some(Err(RangeError::Ignored)) => (response.status(StatusCode::OK), 0, length),
+        Some(Ok((start, end))) => (
+            response
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(CONTENT_RANGE, format!("bytes {start}-{}/{length}", end - 1)),
+            start,
+            end,
+        ),
+        Some(Err(RangeError::Unsatisfiable)) => {
+            return Ok(with_body(
+                response
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(CONTENT_RANGE, format!("bytes */{length}")),
+                Bytes::new(),
+            ));
+        }
+    };
+    let response = response.header(CONTENT_LENGTH, end - start);
+    if request.method() == Method::HEAD {
+        return Ok(with_body(response, Bytes::new()));
+    }
+    file.seek(SeekFrom::Start(start)).await?;
+    let stream = ReaderStream::new(file.take(end - start)).map_ok(Frame::data);
+    Ok(response
+        .body(StreamBody::new(stream).boxed())
+        .expect("Response headers are always valid"))
+}
+
+/// Maps the percent encoded request path onto the directory. None if the path leaves the directory
+fn resolve(directory: &Path, path: &str) -> Option<PathBuf> {
+    let path = percent_decode_str(path).decode_utf8().ok()?;
+    if path.contains('\0') {
+        return None;
+    }
+    let path = Path::new(path.trim_start_matches('/'));
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then(|| directory.join(path))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RangeError {
+    /// The header is malformed or requests multiple ranges. The full file is sent
+    Ignored,
+    Unsatisfiable,
+}
+/// Parses a `Range` header into the start and exclusive end
+fn parse_range(header: &str, length: u64) -> Result<(u64, u64), RangeError> {
+    let range = header
+        .trim()
+        .strip_prefix("bytes=")
+        .filter(|range| !range.contains(','))
+        .ok_or(RangeError::Ignored)?;
+    let (start, end) = range.split_once('-').ok_or(RangeError::Ignored)?;
+    let parse = |value: &str| value.trim().parse::<u64>().map_err(|_| RangeError::Ignored);
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", "") => return Err(RangeError::Ignored),
+        ("", suffix) => {
+            let suffix = parse(suffix)?;
+            if suffix == 0 {
+                return Err(RangeError::Unsatisfiable);
+            }
+            (length.saturating_sub(suffix), length)
+        }
+        (start, "") => (parse(start)?, length),
+        (start, end) => {
+            let (start, end) = (parse(start)?, parse(end)?);
+            if end < start {
+                return Err(RangeError::Ignored);
+            }
+            (start, (end + 1).min(length))
+        }
+    };
+    if start >= length {
+        return Err(RangeError::Unsatisfiable);
+    }
+    Ok((start, end))
+}
+
+fn with_body(response: hyper::http::response::Builder, body: Bytes) -> Response<Body> {
+    response
+        .body(Full::new(body).map_err(|never| match never {}).boxed())
+        .expect("Response headers are always valid")
+}
+fn empty(status: StatusCode) -> Response<Body> {
+    with_body(Response::builder().status(status), Bytes::new())
+}
+#[cfg(test)]
+mod tests {
+    use reqwest::{
+        header::{CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_NONE_MATCH, RANGE},
+        Client, StatusCode,
+    };
+
+    use crate::{
+        game_files::mirror::server::{parse_range, resolve, MirrorServer, RangeError},
+        APIClient,
+    };
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("bytes=0-4", 10), Ok((0, 5)));
+        assert_eq!(parse_range("bytes=5-", 10), Ok((5, 10)));
+        assert_eq!(parse_range("bytes=-3", 10), Ok((7, 10)));
+        assert_eq!(parse_range("bytes=8-100", 10), Ok((8, 10)));
+        assert_eq!(parse_range("bytes=10-", 10), Err(RangeError::Unsatisfiable));
+        assert_eq!(parse_range("bytes=0-1,4-5", 10), Err(RangeError::Ignored));
+        assert_eq!(parse_range("items=0-1", 10), Err(RangeError::Ignored));
+    }
+
+    #[test]
+    fn paths_stay_in_the_mirror() {
+        let mirror = std::path::Path::new("mirror");
+        assert_eq!(
+            resolve(mirror, "/libraries/lib.jar"),
+            Some(mirror.join("libraries/lib.jar"))
+        );
+        assert_eq!(resolve(mirror, "/libraries/../../etc/passwd"), None);
+        assert_eq!(
+            resolve(mirror, "/libraries/my%20lib.jar"),
+            Some(mirror.join("libraries/my lib.jar"))
+        );
+        assert_eq!(resolve(mirror, "/libraries/%2e%2e/%2e%2e/etc/passwd"), None);
+        assert_eq!(resolve(mirror, "/libraries/%00.jar"), None);
+        assert_eq!(resolve(mirror, "/libraries/%ff.jar"), None);
+    }
+
+    #[tokio::test]
+    async fn serve_mirror() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let meta = dir.path().join("launcher_meta/mc/game");
+        std::fs::create_dir_all(&meta)?;
+        std::fs::write(
+            meta.join("version_manifest_v2.json"),
+            serde_json::to_vec(&crate::test::version_manifest_json())?,
+        )?;
+        std::fs::create_dir_all(dir.path().join("libraries"))?;
+        std::fs::write(dir.path().join("libraries/lib.jar"), b"0123456789")?;
+
+        let server = MirrorServer::start(dir.path()).await?;
+        let client = APIClient::new(Client::new(), server.game_files());
+        assert_eq!(client.version_manifest().await?.latest.release, "1.20.5");
+
+        let http = Client::new();
+        let url = format!("{}/libraries/lib.jar", server.base_url());
+        let response = http.get(&url).send().await?;
+        assert_eq!(response.headers()[CONTENT_LENGTH], "10");
+        let etag = response.headers()[ETAG].clone();
+
+        let response = http.get(&url).header(RANGE, "bytes=2-4").send().await?;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[CONTENT_RANGE], "bytes 2-4/10");
+        assert_eq!(response.bytes().await?.as_ref(), b"234");
+
+        let response = http.get(&url).header(IF_NONE_MATCH, etag).send().await?;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response = http.head(&url).send().await?;
+        assert_eq!(response.headers()[CONTENT_LENGTH], "10");
+
+        let large: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        std::fs::write(dir.path().join("libraries/large lib.jar"), &large)?;
+        let url = format!("{}/libraries/large%20lib.jar", server.base_url());
+        assert_eq!(http.get(&url).send().await?.bytes().await?.as_ref(), large);
+        let response = http
+            .get(&url)
+            .header(RANGE, "bytes=100000-100009")
+            .send()
+            .await?;
+        assert_eq!(response.bytes().await?.as_ref(), &large[100_000..100_010]);
+
+        let escape = format!("{}/libraries/../../etc/passwd", server.base_url());
+        assert_eq!(
+            http.get(escape).send().await?.status(),
+            StatusCode::NOT_FOUND
+        );
+        Ok(())
+    }
+}
@@ -1,24 +1,81 @@
 pub mod assets;
+pub mod cache;
+pub mod installation;
+pub mod jar;
+pub mod java_runtime;
+pub mod manifest_watcher;
+pub mod mirror;
 pub mod release;
+pub mod release_fetch;
+pub mod rewrite;
+pub mod version_id;
 pub mod version_manifest;
 pub mod version_type;
 
 use std::borrow::Cow;
 
-use reqwest::Url;
+use reqwest::{Response, Url};
 use serde::{Deserialize, Serialize};
 
 use self::version_manifest::VersionManifest;
-use crate::{APIClient, Error};
+use crate::{http::IntoResult, utils::send_with_failover, APIClient, Error};
 pub static RESOURCE_URL_BASE: &str = "https://resources.download.minecraft.net";
 pub static LIBRARY_URL_BASE: &str = "https://libraries.minecraft.net";
 pub static LAUNCHER_META_URL_BASE: &str = "https://piston-meta.mojang.com";
+/// Hosts serving the launcher meta and the files referenced by it
+pub static LAUNCHER_META_HOSTS: &[&str] = &[
+    "piston-meta.mojang.com",
+    "piston-data.mojang.com",
+    "launchermeta.mojang.com",
+    "launcher.mojang.com",
+];
+/// The path of the version manifest relative to the launcher meta
+pub static VERSION_MANIFEST_PATH: &str = "mc/game/version_manifest_v2.json";
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GameFilesAPIBuilder {
     pub resource_base: Cow<'static, str>,
     pub library_base: Cow<'static, str>,
     pub launcher_meta: Cow<'static, str>,
+    /// Tried in order when the resource base can not be reached or responds with a 5xx
+    #[serde(default)]
+    pub resource_mirrors: Vec<Cow<'static, str>>,
+    /// Tried in order when the library base can not be reached or responds with a 5xx
+    #[serde(default)]
+    pub library_mirrors: Vec<Cow<'static, str>>,
+    /// Tried in order when the launcher meta can not be reached or responds with a 5xx
+    #[serde(default)]
+    pub launcher_meta_mirrors: Vec<Cow<'static, str>>,
+}
+
+/// The kinds of servers game files are pulled from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Endpoint {
+    /// Version manifest, release JSONs, asset indexes, jars and log configs
+    LauncherMeta,
+    /// Asset objects
+    Resource,
+    /// Libraries
+    Library,
+}
+impl Endpoint {
+    pub const ALL: [Endpoint; 3] = [
+        Endpoint::LauncherMeta,
+        Endpoint::Resource,
+        Endpoint::Library,
+    ];
+    /// The endpoint Mojang serves from the host
+    pub fn from_mojang_host(host: &str) -> Option<Self> {
+        if LAUNCHER_META_HOSTS.contains(&host) {
+            Some(Endpoint::LauncherMeta)
+        } else if RESOURCE_URL_BASE.strip_prefix("https://") == Some(host) {
+            Some(Endpoint::Resource)
+        } else if LIBRARY_URL_BASE.strip_prefix("https://") == Some(host) {
+            Some(Endpoint::Library)
+        } else {
+            None
+        }
+    }
 }
 
 impl Default for GameFilesAPIBuilder {
@@ -27,6 +84,9 @@ impl Default for GameFilesAPIBuilder {
             resource_base: Cow::Borrowed(RESOURCE_URL_BASE),
             library_base: Cow::Borrowed(LIBRARY_URL_BASE),
             launcher_meta: Cow::Borrowed(LAUNCHER_META_URL_BASE),
+            resource_mirrors: Vec::new(),
+            library_mirrors: Vec::new(),
+            launcher_meta_mirrors: Vec::new(),
         }
     }
 }
@@ -34,35 +94,183 @@ impl Default for GameFilesAPIBuilder {
 impl APIClient {
     //TODO include access point for Game File API
     pub async fn version_manifest(&self) -> Result<VersionManifest, Error> {
-        self.process_json(
-            self.http_client.get(
-                self.game_files
-                    .create_launcher_url("mc/game/version_manifest_v2.json"),
-            ),
-        )
-        .await
+        let (response, _) = self
+            .get_from_endpoint(Endpoint::LauncherMeta, VERSION_MANIFEST_PATH)
+            .await?;
+        let text = response.into_result::<Error>().await?.text().await?;
+        let mut manifest: VersionManifest = serde_json::from_str(&text)?;
+        manifest.rewrite_urls(&self.game_files);
+        Ok(manifest)
+    }
+    /// Requests the path from the endpoint. The mirrors of the endpoint are tried in order on connection errors and 5xx responses.
+    ///
+    /// Returns the response and the URL that served it
+    pub async fn get_from_endpoint(
+        &self,
+        endpoint: Endpoint,
+        path: &str,
+    ) -> Result<(Response, Url), Error> {
+        let urls = self.game_files.create_urls(endpoint, path)?;
+        send_with_failover(&urls, |url| self.http_client.get(url)).await
     }
 }
 
 impl GameFilesAPIBuilder {
+    /// Points every endpoint at a mirror created by [MirrorBuilder](mirror::MirrorBuilder)
+    pub fn from_mirror(mirror_base: &str) -> Self {
+        let mirror_base = mirror_base.trim_end_matches('/');
+        let base = |endpoint: Endpoint| {
+            Cow::Owned(format!(
+                "{mirror_base}/{}",
+                mirror::directory_name(endpoint)
+            ))
+        };
+        GameFilesAPIBuilder {
+            resource_base: base(Endpoint::Resource),
+            library_base: base(Endpoint::Library),
+            launcher_meta: base(Endpoint::LauncherMeta),
+            ..Default::default()
+        }
+    }
+    /// Adds a mirror to the end of the endpoint's fallback list
+    pub fn with_mirror(mut self, endpoint: Endpoint, base: impl Into<Cow<'static, str>>) -> Self {
+        match endpoint {
+            Endpoint::LauncherMeta => self.launcher_meta_mirrors.push(base.into()),
+            Endpoint::Resource => self.resource_mirrors.push(base.into()),
+            Endpoint::Library => self.library_mirrors.push(base.into()),
+        }
+        self
+    }
+    /// The configured base URL of the endpoint
+    pub fn base(&self, endpoint: Endpoint) -> &str {
+        match endpoint {
+            Endpoint::LauncherMeta => &self.launcher_meta,
+            Endpoint::Resource => &self.resource_base,
+            Endpoint::Library => &self.library_base,
+        }
+    }
+    /// The base URL followed by the mirrors of the endpoint
+    pub fn bases(&self, endpoint: Endpoint) -> impl Iterator<Item = &str> {
+        let mirrors = match endpoint {
+            Endpoint::LauncherMeta => &self.launcher_meta_mirrors,
+            Endpoint::Resource => &self.resource_mirrors,
+            Endpoint::Library => &self.library_mirrors,
+        };
+        std::iter::once(self.base(endpoint)).chain(mirrors.iter().map(AsRef::as_ref))
+    }
+    /// Generates a URL for the path on the base of the endpoint
+    pub fn create_url(&self, endpoint: Endpoint, path: &str) -> Result<Url, url::ParseError> {
+        join_url(self.base(endpoint), path)
+    }
+    /// Generates a URL for the path on the base and every mirror of the endpoint. In the order they should be tried
+    pub fn create_urls(&self, endpoint: Endpoint, path: &str) -> Result<Vec<Url>, url::ParseError> {
+        self.bases(endpoint)
+            .map(|base| join_url(base, path))
+            .collect()
+    }
+    /// Finds the endpoint serving the URL and the path of the file relative to it.
+    ///
+    /// URLs below a configured base are checked first. Then the hosts Mojang uses
+    pub fn endpoint_path(&self, url: &Url) -> Option<(Endpoint, String)> {
+        for endpoint in Endpoint::ALL {
+            for base in self.bases(endpoint) {
+                let path = url
+                    .as_str()
+                    .strip_prefix(base.trim_end_matches('/'))
+                    .and_then(|path| path.strip_prefix('/'));
+                if let Some(path) = path {
+                    return Some((endpoint, path.to_owned()));
+                }
+            }
+        }
+        let endpoint = Endpoint::from_mojang_host(url.host_str()?)?;
+        Some((endpoint, url.path().trim_start_matches('/').to_owned()))
+    }
     /// Generates a Resource URL from the path given
-    pub fn create_resource_url<S: AsRef<str>>(&self, s: S) -> Url {
-        Url::parse(&format!("{}/{}", &self.resource_base, s.as_ref())).unwrap()
+    pub fn create_resource_url<S: AsRef<str>>(&self, s: S) -> Result<Url, url::ParseError> {
+        self.create_url(Endpoint::Resource, s.as_ref())
     }
     /// Generates a Library URL from the path given
-    pub fn create_library_url<S: AsRef<str>>(&self, s: S) -> Url {
-        Url::parse(&format!("{}/{}", &self.library_base, s.as_ref())).unwrap()
+    pub fn create_library_url<S: AsRef<str>>(&self, s: S) -> Result<Url, url::ParseError> {
+        self.create_url(Endpoint::Library, s.as_ref())
     }
     /// Generates a Launcher URL from the path given
-    pub fn create_launcher_url<S: AsRef<str>>(&self, s: S) -> Url {
-        Url::parse(&format!("{}/{}", &self.launcher_meta, s.as_ref())).unwrap()
+    pub fn create_launcher_url<S: AsRef<str>>(&self, s: S) -> Result<Url, url::ParseError> {
+        self.create_url(Endpoint::LauncherMeta, s.as_ref())
     }
 }
+pub(crate) fn join_url(base: &str, path: &str) -> Result<Url, url::ParseError> {
+    Url::parse(&format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    ))
+}
 #[cfg(test)]
 mod tests {
-    use std::time::Duration;
+    use std::borrow::Cow;
+
+    use reqwest::Client;
+
+    use crate::{
+        game_files::{
+            release_fetch::FetchOptions, Endpoint, GameFilesAPIBuilder, VERSION_MANIFEST_PATH,
+        },
+        APIClient,
+    };
 
-    use tokio::time::sleep;
+    #[test]
+    fn mirror_urls() {
+        let game_files = GameFilesAPIBuilder::default()
+            .with_mirror(Endpoint::Library, "https://mirror.example/maven/")
+            .with_mirror(Endpoint::Library, "not a url");
+        assert_eq!(
+            game_files
+                .create_library_url("com/example/lib.jar")
+                .unwrap()
+                .as_str(),
+            "https://libraries.minecraft.net/com/example/lib.jar"
+        );
+        assert!(game_files
+            .create_urls(Endpoint::Library, "com/example/lib.jar")
+            .is_err());
+        let url = "https://mirror.example/maven/com/example/lib.jar"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            game_files.endpoint_path(&url),
+            Some((Endpoint::Library, "com/example/lib.jar".to_owned()))
+        );
+    }
+
+    #[tokio::test]
+    async fn mirror_failover() -> anyhow::Result<()> {
+        crate::test::setup();
+        let failing = crate::test::TestServer::start().await?;
+        failing.fail_with(503);
+        let working = crate::test::TestServer::start().await?;
+        working.insert(
+            &format!("/{VERSION_MANIFEST_PATH}"),
+            serde_json::to_vec(&crate::test::version_manifest_json())?,
+        );
+        let game_files = GameFilesAPIBuilder {
+            launcher_meta: Cow::Borrowed("http://127.0.0.1:9"),
+            ..Default::default()
+        }
+        .with_mirror(Endpoint::LauncherMeta, failing.base().to_owned())
+        .with_mirror(Endpoint::LauncherMeta, working.base().to_owned());
+        let client = APIClient::new(Client::new(), game_files);
+
+        let (_, served_by) = client
+            .get_from_endpoint(Endpoint::LauncherMeta, VERSION_MANIFEST_PATH)
+            .await?;
+        assert_eq!(
+            served_by.as_str(),
+            working.url(&format!("/{VERSION_MANIFEST_PATH}"))
+        );
+        assert_eq!(client.version_manifest().await?.latest.release, "1.20.5");
+        Ok(())
+    }
 
     #[tokio::test]
     async fn version_manifest_v2() -> anyhow::Result<()> {
@@ -113,19 +321,19 @@ mod tests {
         let client = crate::test::setup();
         let version_manifest = client.version_manifest().await?;
         println!("Latest Release Info {:#?}", version_manifest.latest);
-        for version in &version_manifest.versions {
-            match version.get_release(&client).await {
+        let mut results = version_manifest.stream_releases(&client, FetchOptions::default());
+        while let Some(fetched) = results.recv().await {
+            match fetched.result {
                 Ok(ok) => {
                     println!("Version Parsed {ok:#?}")
                 }
                 Err(err) => {
                     eprintln!(
                         "Could not read release data from {:?} \n Error \n {err:#?}",
-                        version
+                        fetched.version
                     );
                 }
             }
-            //sleep(Duration::from_secs(1)).await
         }
 
         Ok(())
@@ -27,6 +27,15 @@ pub struct RuledArgument {
     /// Rules required
     rules: Vec<Rule>,
 }
+impl Argument {
+    /// The values added. Regardless of the rules
+    pub fn values(&self) -> &[String] {
+        match self {
+            Argument::RuledArgument(argument) => &argument.value,
+            Argument::Simple(value) => std::slice::from_ref(value),
+        }
+    }
+}
 impl<'a> From<&'a str> for Argument {
     fn from(value: &'a str) -> Self {
         Argument::Simple(value.to_owned())
@@ -7,6 +7,7 @@ use serde_json::Value;
 use crate::{
     game_files::{
         release::{argument::Arguments, library::Library},
+        version_id::VersionId,
         version_type::VersionType,
     },
     mojang_time,
@@ -43,6 +44,10 @@ pub struct ReleaseData {
     pub other: HashMap<String, Value>,
 }
 impl ReleaseData {
+    /// Parses the [id](ReleaseData::id)
+    pub fn version_id(&self) -> VersionId {
+        VersionId::parse(&self.id)
+    }
     pub fn has_server(&self) -> bool {
         self.downloads.server.is_some()
     }
@@ -74,14 +79,14 @@ pub struct Downloads {
     pub client_mapping: Option<Download>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct JavaVersion {
     pub component: String,
     #[serde(rename = "majorVersion")]
     pub major_version: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Logging {
     pub argument: String,
     pub file: LoggingFile,
@@ -89,7 +94,7 @@ pub struct Logging {
     pub logging_type: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct LoggingFile {
     pub id: String,
     pub sha1: String,
@@ -0,0 +1,334 @@
+//! Compares two releases. Such as when moving a modpack to a new version
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Formatter},
+};
+
+use serde::Serialize;
+
+use crate::game_files::release::{
+    argument::Argument,
+    data::{JavaVersion, ReleaseData},
+    library::MavenCoordinate,
+};
+
+/// A value that differs between the releases
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValueChange<T> {
+    pub from: T,
+    pub to: T,
+}
+impl<T: PartialEq + Clone> ValueChange<T> {
+    fn between(from: &T, to: &T) -> Option<Self> {
+        (from != to).then(|| Self {
+            from: from.clone(),
+            to: to.clone(),
+        })
+    }
+}
+
+/// A library found in both releases with other versions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LibraryUpgrade {
+    /// `group:artifact[:classifier]`
+    pub library: String,
+    /// Usually one version. Some releases list a library once per platform
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+}
+
+/// Libraries are matched by `group:artifact[:classifier]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LibraryChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Includes downgrades
+    pub upgraded: Vec<LibraryUpgrade>,
+}
+impl LibraryChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.upgraded.is_empty()
+    }
+}
+
+/// Arguments are compared by value. Rules are not compared
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ArgumentChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+impl ArgumentChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// The result of [ReleaseData::diff]. Displays as a text report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReleaseDiff {
+    pub from: String,
+    pub to: String,
+    pub libraries: LibraryChanges,
+    pub java_version: Option<ValueChange<JavaVersion>>,
+    pub main_class: Option<ValueChange<String>>,
+    /// Versions before 1.13 list the game arguments in `minecraftArguments`
+    pub game_arguments: ArgumentChanges,
+    pub jvm_arguments: ArgumentChanges,
+    pub asset_index: Option<ValueChange<String>>,
+    pub compliance_level: Option<ValueChange<u8>>,
+}
+impl ReleaseDiff {
+    pub fn is_empty(&self) -> bool {
+        self.libraries.is_empty()
+            && self.java_version.is_none()
+            && self.main_class.is_none()
+            && self.game_arguments.is_empty()
+            && self.jvm_arguments.is_empty()
+            && self.asset_index.is_none()
+            && self.compliance_level.is_none()
+    }
+}
+
+impl ReleaseData {
+    /// Lists what changed from this release to `to`
+    pub fn diff(&self, to: &ReleaseData) -> ReleaseDiff {
+        ReleaseDiff {
+            from: self.id.clone(),
+            to: to.id.clone(),
+            libraries: library_changes(self, to),
+            java_version: ValueChange::between(&self.java_version, &to.java_version),
+            main_class: ValueChange::between(&self.main_class, &to.main_class),
+            game_arguments: argument_changes(self.game_arguments(), to.game_arguments()),
+            jvm_arguments: argument_changes(self.jvm_arguments(), to.jvm_arguments()),
+            asset_index: ValueChange::between(&self.asset_index.id, &to.asset_index.id),
+            compliance_level: ValueChange::between(&self.compliance_level, &to.compliance_level),
+        }
+    }
+    /// The values of the game arguments. Read from `minecraftArguments` on versions before 1.13
+    pub fn game_arguments(&self) -> Vec<&str> {
+        match &self.arguments {
+            Some(arguments) => argument_values(&arguments.game),
+            None => self
+                .other
+                .get("minecraftArguments")
+                .and_then(|arguments| arguments.as_str())
+                .map(|arguments| arguments.split_whitespace().collect())
+                .unwrap_or_default(),
+        }
+    }
+    /// The values of the JVM arguments. Versions before 1.13 do not list them
+    pub fn jvm_arguments(&self) -> Vec<&str> {
+        self.arguments
+            .as_ref()
+            .map(|arguments| argument_values(&arguments.jvm))
+            .unwrap_or_default()
+    }
+}
+
+fn argument_values(arguments: &[Argument]) -> Vec<&str> {
+    arguments
+        .iter()
+        .flat_map(|argument| argument.values())
+        .map(String::as_str)
+        .collect()
+}
+
+fn argument_changes(from: Vec<&str>, to: Vec<&str>) -> ArgumentChanges {
+    let difference = |a: &[&str], b: &[&str]| {
+        let mut seen = BTreeSet::new();
+        a.iter()
+            .filter(|value| !b.contains(value) && seen.insert(**value))
+            .map(|value| (*value).to_owned())
+            .collect()
+    };
+    ArgumentChanges {
+        added: difference(&to, &from),
+        removed: difference(&from, &to),
+    }
+}
+
+/// The versions of each library keyed by `group:artifact[:classifier]`
+fn library_versions(release: &ReleaseData) -> BTreeMap<String, BTreeSet<String>> {
+    let mut libraries: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for library in &release.libraries {
+        let (key, version) = match MavenCoordinate::parse(&library.name) {
+            Some(coordinate) => (coordinate.key(), coordinate.version),
+            None => (library.name.clone(), String::new()),
+        };
+        libraries.entry(key).or_default().insert(version);
+    }
+    libraries
+}
+
+fn library_changes(from: &ReleaseData, to: &ReleaseData) -> LibraryChanges {
+    let from = library_versions(from);
+    let to = library_versions(to);
+    let coordinates = |key: &str, versions: &BTreeSet<String>| {
+        versions
+            .iter()
+            .map(|version| match version.as_str() {
+                "" => key.to_owned(),
+                version => with_version(key, version),
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut changes = LibraryChanges::default();
+    for (key, versions) in &to {
+        match from.get(key) {
+            None => changes.added.extend(coordinates(key, versions)),
+            Some(previous) if previous != versions => changes.upgraded.push(LibraryUpgrade {
+                library: key.clone(),
+                from: previous.iter().cloned().collect(),
+                to: versions.iter().cloned().collect(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, versions) in &from {
+        if !to.contains_key(key) {
+            changes.removed.extend(coordinates(key, versions));
+        }
+    }
+    changes
+}
+
+/// Places the version back between the artifact and the classifier
+fn with_version(key: &str, version: &str) -> String {
+    let mut parts: Vec<&str> = key.splitn(3, ':').collect();
+    parts.insert(2.min(parts.len()), version);
+    parts.join(":")
+}
+
+impl Display for ReleaseDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} -> {}", self.from, self.to)?;
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+        if let Some(java) = &self.java_version {
+            writeln!(
+                f,
+                "Java: {} ({}) -> {} ({})",
+                java.from.major_version,
+                java.from.component,
+                java.to.major_version,
+                java.to.component
+            )?;
+        }
+        if let Some(main_class) = &self.main_class {
+            writeln!(f, "Main class: {} -> {}", main_class.from, main_class.to)?;
+        }
+        if let Some(asset_index) = &self.asset_index {
+            writeln!(f, "Asset index: {} -> {}", asset_index.from, asset_index.to)?;
+        }
+        if let Some(level) = &self.compliance_level {
+            writeln!(f, "Compliance level: {} -> {}", level.from, level.to)?;
+        }
+        if !self.libraries.is_empty() {
+            writeln!(f, "Libraries:")?;
+            for library in &self.libraries.added {
+                writeln!(f, "  + {library}")?;
+            }
+            for library in &self.libraries.removed {
+                writeln!(f, "  - {library}")?;
+            }
+            for upgrade in &self.libraries.upgraded {
+                writeln!(
+                    f,
+                    "  ~ {} {} -> {}",
+                    upgrade.library,
+                    upgrade.from.join(", "),
+                    upgrade.to.join(", ")
+                )?;
+            }
+        }
+        for (name, changes) in [
+            ("Game arguments", &self.game_arguments),
+            ("JVM arguments", &self.jvm_arguments),
+        ] {
+            if changes.is_empty() {
+                continue;
+            }
+            writeln!(f, "{name}:")?;
+            for argument in &changes.added {
+                writeln!(f, "  + {argument}")?;
+            }
+            for argument in &changes.removed {
+                writeln!(f, "  - {argument}")?;
+            }
+        }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::game_files::release::{data::ReleaseData, diff::with_version};
+
+    fn library(name: &str) -> serde_json::Value {
+        json!({ "name": name, "downloads": {} })
+    }
+
+    #[test]
+    fn diff() -> anyhow::Result<()> {
+        let mut from = crate::test::release_json("1.12.2");
+        from["minecraftArguments"] = json!("--username ${auth_player_name} --tweakClass optifine");
+        from["libraries"] = json!([
+            library("com.mojang:realms:1.10.22"),
+            library("org.lwjgl.lwjgl:lwjgl:2.9.4-nightly-20150209"),
+            library("com.google.guava:guava:21.0"),
+            library("org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209")
+        ]);
+        let mut to = crate::test::release_json("1.20.5");
+        to["arguments"] = json!({
+            "game": ["--username", "${auth_player_name}", { "rules": [{ "action": "allow", "features": { "has_quick_plays_support": true } }], "value": ["--quickPlayPath", "${quickPlayPath}"] }],
+            "jvm": ["-cp", "${classpath}"]
+        });
+        to["javaVersion"] = json!({ "component": "java-runtime-delta", "majorVersion": 21 });
+        to["libraries"] = json!([
+            library("com.google.guava:guava:32.1.2-jre"),
+            library("org.lwjgl:lwjgl:3.3.3"),
+            library("org.lwjgl:lwjgl:3.3.3:natives-linux")
+        ]);
+        let from: ReleaseData = serde_json::from_value(from)?;
+        let to: ReleaseData = serde_json::from_value(to)?;
+
+        let diff = from.diff(&to);
+        assert_eq!(
+            diff.libraries.added,
+            [
+                "org.lwjgl:lwjgl:3.3.3",
+                "org.lwjgl:lwjgl:3.3.3:natives-linux"
+            ]
+        );
+        assert_eq!(diff.libraries.removed.len(), 3);
+        assert_eq!(diff.libraries.upgraded.len(), 1);
+        assert_eq!(diff.libraries.upgraded[0].library, "com.google.guava:guava");
+        assert_eq!(diff.libraries.upgraded[0].to, ["32.1.2-jre"]);
+        assert_eq!(diff.java_version.as_ref().unwrap().to.major_version, 21);
+        assert_eq!(diff.main_class, None);
+        assert_eq!(diff.compliance_level, None);
+        assert_eq!(diff.asset_index.as_ref().unwrap().to, "1.20.5");
+        assert_eq!(
+            diff.game_arguments.added,
+            ["--quickPlayPath", "${quickPlayPath}"]
+        );
+        assert_eq!(diff.game_arguments.removed, ["--tweakClass", "optifine"]);
+        assert_eq!(diff.jvm_arguments.added, ["-cp", "${classpath}"]);
+
+        let json = serde_json::to_value(&diff)?;
+        assert_eq!(json["java_version"]["from"]["majorVersion"], 8);
+        let text = diff.to_string();
+        assert!(
+            text.starts_with("1.12.2 -> 1.20.5\nJava: 8 (jre-legacy) -> 21 (java-runtime-delta)\n")
+        );
+        assert!(text.contains("  ~ com.google.guava:guava 21.0 -> 32.1.2-jre\n"));
+        assert!(from.diff(&from).is_empty());
+        assert_eq!(
+            with_version("a:b:natives-linux", "1.0"),
+            "a:b:1.0:natives-linux"
+        );
+        Ok(())
+    }
+}
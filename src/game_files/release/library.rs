@@ -1,45 +1,23 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
-use crate::game_files::release::{
-    data::ReleaseData,
-    rule::{Rule, RuleType},
-};
+use crate::game_files::release::{data::ReleaseData, platform::Platform, rule::Rule};
 
 impl ReleaseData {
-    pub fn get_libraries_to_download(
-        &self,
-        os: String,
-        arch: String,
-        version: Option<String>,
-    ) -> Vec<&Library> {
-        let mut libraries = Vec::new();
-        for library in self.libraries.iter() {
-            if let Some(rules) = library.rules.as_ref() {
-                for rule in rules {
-                    let mut should_download = false;
-                    match rule.action {
-                        RuleType::Allow => {
-                            if rule.should_do_os(os.as_str(), arch.as_str(), version.clone()) {
-                                should_download = true;
-                            }
-                        }
-                        RuleType::Disallow => {
-                            if rule.should_do_os(os.as_str(), arch.as_str(), version.clone()) {
-                                should_download = false;
-                            }
-                        }
-                    }
-                    if should_download {
-                        libraries.push(library)
-                    }
-                }
-            } else {
-                libraries.push(library)
-            }
-        }
-        libraries
+    /// The libraries used on the platform. Which does not have to be the host
+    pub fn get_libraries_to_download(&self, platform: &Platform) -> Vec<&Library> {
+        self.libraries
+            .iter()
+            .filter(|library| library.applies_to(platform))
+            .collect()
+    }
+    /// The native artifacts used on the platform
+    pub fn get_natives_to_download(&self, platform: &Platform) -> Vec<&Artifact> {
+        self.get_libraries_to_download(platform)
+            .into_iter()
+            .filter_map(|library| library.native_artifact(platform))
+            .collect()
     }
 }
 
@@ -52,6 +30,53 @@ pub struct Library {
     pub natives: Option<HashMap<String, String>>,
 }
 
+impl Library {
+    /// Parses the [name](Library::name)
+    pub fn coordinate(&self) -> Option<MavenCoordinate> {
+        MavenCoordinate::parse(&self.name)
+    }
+}
+
+/// `group:artifact:version[:classifier]`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MavenCoordinate {
+    pub group: String,
+    pub artifact: String,
+    pub version: String,
+    pub classifier: Option<String>,
+}
+impl MavenCoordinate {
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split(':');
+        let coordinate = Self {
+            group: parts.next()?.to_owned(),
+            artifact: parts.next()?.to_owned(),
+            version: parts.next()?.to_owned(),
+            classifier: parts.next().map(str::to_owned),
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(coordinate)
+    }
+    /// `group:artifact[:classifier]`. Identifies the library across versions
+    pub fn key(&self) -> String {
+        match &self.classifier {
+            Some(classifier) => format!("{}:{}:{classifier}", self.group, self.artifact),
+            None => format!("{}:{}", self.group, self.artifact),
+        }
+    }
+}
+impl Display for MavenCoordinate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.group, self.artifact, self.version)?;
+        if let Some(classifier) = &self.classifier {
+            write!(f, ":{classifier}")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Artifact {
     pub sha1: String,
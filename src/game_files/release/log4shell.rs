@@ -0,0 +1,286 @@
+//! Mitigation for [CVE-2021-44228](https://www.minecraft.net/en-us/article/important-message--security-vulnerability-java-edition)
+//!
+//! Versions 1.7 through 1.18 ship a vulnerable log4j. Versions using log4j 2.10 or newer can disable message lookups with a JVM flag.
+//! Older versions need the patched log config published by Mojang.
+use tracing::warn;
+
+use crate::game_files::{
+    release::{
+        argument::Argument,
+        data::{Logging, LoggingFile, ReleaseData},
+        logging::CLIENT_LOGGING,
+    },
+    version_id::ReleaseNumber,
+};
+
+pub static LOG4J_CORE: &str = "org.apache.logging.log4j:log4j-core";
+/// Disables message lookups in log4j 2.10 and newer
+pub static FORMAT_MSG_NO_LOOKUPS: &str = "-Dlog4j2.formatMsgNoLookups=true";
+
+/// Patched config for versions 1.7 through 1.11.2 (log4j 2.0-beta9)
+pub fn patched_config_1_7() -> LoggingFile {
+    LoggingFile {
+        id: "client-1.7.xml".to_owned(),
+        sha1: "50c9cc4af6d853d9fc137c84bcd153e2bd3a9a82".to_owned(),
+        size: 966,
+        url: "https://piston-data.mojang.com/v1/objects/50c9cc4af6d853d9fc137c84bcd153e2bd3a9a82/client-1.7.xml".to_owned(),
+    }
+}
+/// Patched config for versions 1.12 through 1.16.5 (log4j 2.8.1)
+pub fn patched_config_1_12() -> LoggingFile {
+    LoggingFile {
+        id: "client-1.12.xml".to_owned(),
+        sha1: "bd65e7d2e3c237be76cfbef4c2405033d7f91521".to_owned(),
+        size: 888,
+        url: "https://piston-data.mojang.com/v1/objects/bd65e7d2e3c237be76cfbef4c2405033d7f91521/client-1.12.xml".to_owned(),
+    }
+}
+
+/// How a version is protected against Log4Shell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Log4ShellMitigation {
+    /// Replace the client log config with a patched one
+    PatchedConfig(LoggingFile),
+    /// Add [FORMAT_MSG_NO_LOOKUPS] to the JVM arguments
+    JvmFlag,
+}
+impl Log4ShellMitigation {
+    /// The JVM argument required by the mitigation
+    pub fn jvm_argument(&self) -> Option<&'static str> {
+        match self {
+            Log4ShellMitigation::JvmFlag => Some(FORMAT_MSG_NO_LOOKUPS),
+            Log4ShellMitigation::PatchedConfig(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Log4ShellStatus {
+    /// The version does not use a vulnerable log4j
+    NotAffected,
+    /// The version is vulnerable but the mitigation is already in place
+    Mitigated(Log4ShellMitigation),
+    /// The version is vulnerable and the mitigation must be applied
+    Affected(Log4ShellMitigation),
+}
+
+impl ReleaseData {
+    /// The version of `log4j-core` found within the libraries
+    pub fn log4j_version(&self) -> Option<&str> {
+        self.libraries.iter().find_map(|library| {
+            library
+                .name
+                .strip_prefix(LOG4J_CORE)
+                .and_then(|version| version.strip_prefix(':'))
+        })
+    }
+
+    /// Checks if the version is vulnerable to Log4Shell.
+    ///
+    /// The log4j version is read from the libraries. If the version does not list log4j the version id is used instead.
+    pub fn log4shell_status(&self) -> Log4ShellStatus {
+        let Some(mitigation) = self.required_log4shell_mitigation() else {
+            return Log4ShellStatus::NotAffected;
+        };
+        let mitigated = match &mitigation {
+            Log4ShellMitigation::PatchedConfig(patched) => self
+                .client_logging()
+                .is_some_and(|logging| logging.file.sha1.eq_ignore_ascii_case(&patched.sha1)),
+            Log4ShellMitigation::JvmFlag => self.arguments.as_ref().is_some_and(|arguments| {
+                arguments
+                    .jvm
+                    .contains(&Argument::Simple(FORMAT_MSG_NO_LOOKUPS.to_owned()))
+            }),
+        };
+        if mitigated {
+            Log4ShellStatus::Mitigated(mitigation)
+        } else {
+            Log4ShellStatus::Affected(mitigation)
+        }
+    }
+
+    /// Applies the Log4Shell mitigation to the release.
+    ///
+    /// The client log config is replaced with the patched one. Or [FORMAT_MSG_NO_LOOKUPS] is added to the JVM arguments.
+    /// Versions without JVM arguments must add [Log4ShellMitigation::jvm_argument] to the launch command themselves.
+    ///
+    /// Returns the status of the release before the mitigation was applied
+    pub fn apply_log4shell_mitigation(&mut self) -> Log4ShellStatus {
+        let status = self.log4shell_status();
+        let Log4ShellStatus::Affected(mitigation) = &status else {
+            return status;
+        };
+        match mitigation {
+            Log4ShellMitigation::PatchedConfig(file) => {
+                self.logging.insert(
+                    CLIENT_LOGGING.to_owned(),
+                    Logging {
+                        argument: "-Dlog4j.configurationFile=${path}".to_owned(),
+                        file: file.clone(),
+                        logging_type: "log4j2-xml".to_owned(),
+                    },
+                );
+            }
+            Log4ShellMitigation::JvmFlag => match self.arguments.as_mut() {
+                Some(arguments) => arguments.jvm.push(FORMAT_MSG_NO_LOOKUPS.into()),
+                None => {
+                    warn!(id = ?self.id, "Release has no JVM arguments to add the Log4Shell flag to");
+                }
+            },
+        }
+        status
+    }
+
+    fn required_log4shell_mitigation(&self) -> Option<Log4ShellMitigation> {
+        let mitigation = match self.log4j_version().and_then(parse_log4j_version) {
+            Some((2, minor)) if minor >= 15 => return None,
+            Some((2, minor)) if minor >= 10 => Log4ShellMitigation::JvmFlag,
+            Some((2, 0)) => Log4ShellMitigation::PatchedConfig(patched_config_1_7()),
+            Some((2, _)) => Log4ShellMitigation::PatchedConfig(patched_config_1_12()),
+            Some(_) => return None,
+            None => match self.version_id().release_target()? {
+                ReleaseNumber {
+                    major: 1,
+                    minor: 7..=11,
+                    ..
+                } => Log4ShellMitigation::PatchedConfig(patched_config_1_7()),
+                ReleaseNumber {
+                    major: 1,
+                    minor: 12..=16,
+                    ..
+                } => Log4ShellMitigation::PatchedConfig(patched_config_1_12()),
+                ReleaseNumber {
+                    major: 1,
+                    minor: 17,
+                    ..
+                }
+                | ReleaseNumber {
+                    major: 1,
+                    minor: 18,
+                    patch: 0,
+                } => Log4ShellMitigation::JvmFlag,
+                _ => return None,
+            },
+        };
+        Some(mitigation)
+    }
+}
+
+/// Parses the major and minor of a log4j version such as `2.0-beta9` or `2.14.1`
+fn parse_log4j_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.split(['.', '-']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::game_files::release::{
+        log4shell::{patched_config_1_12, patched_config_1_7, FORMAT_MSG_NO_LOOKUPS},
+        Argument, Log4ShellMitigation, Log4ShellStatus, ReleaseData,
+    };
+
+    fn release(id: &str, log4j: Option<&str>, logging_sha1: Option<&str>) -> ReleaseData {
+        let mut release = crate::test::release_json(id);
+        if let Some(log4j) = log4j {
+            release["libraries"] = json!([{
+                "downloads": {
+                    "artifact": {
+                        "path": format!("org/apache/logging/log4j/log4j-core/{log4j}/log4j-core-{log4j}.jar"),
+                        "sha1": "0000000000000000000000000000000000000000",
+                        "size": 0,
+                        "url": format!("https://libraries.minecraft.net/org/apache/logging/log4j/log4j-core/{log4j}/log4j-core-{log4j}.jar")
+                    }
+                },
+                "name": format!("org.apache.logging.log4j:log4j-core:{log4j}")
+            }]);
+        }
+        if let Some(sha1) = logging_sha1 {
+            release["logging"] = json!({
+                "client": {
+                    "argument": "-Dlog4j.configurationFile=${path}",
+                    "file": {
+                        "id": "client-1.12.xml",
+                        "sha1": sha1,
+                        "size": 877,
+                        "url": format!("https://piston-data.mojang.com/v1/objects/{sha1}/client-1.12.xml")
+                    },
+                    "type": "log4j2-xml"
+                }
+            });
+        }
+        serde_json::from_value(release).unwrap()
+    }
+
+    #[test]
+    fn not_affected() {
+        assert_eq!(
+            release("1.6.4", None, None).log4shell_status(),
+            Log4ShellStatus::NotAffected
+        );
+        assert_eq!(
+            release("1.18.2", Some("2.17.0"), None).log4shell_status(),
+            Log4ShellStatus::NotAffected
+        );
+        // Snapshots can only be detected through the libraries
+        assert_eq!(
+            release("21w37a", None, None).log4shell_status(),
+            Log4ShellStatus::NotAffected
+        );
+    }
+
+    #[test]
+    fn patched_config() {
+        let mut legacy = release("1.8.9", Some("2.0-beta9"), None);
+        assert_eq!(
+            legacy.apply_log4shell_mitigation(),
+            Log4ShellStatus::Affected(Log4ShellMitigation::PatchedConfig(patched_config_1_7()))
+        );
+        assert_eq!(
+            legacy.client_logging().map(|logging| &logging.file),
+            Some(&patched_config_1_7())
+        );
+        assert!(matches!(
+            legacy.log4shell_status(),
+            Log4ShellStatus::Mitigated(_)
+        ));
+
+        let mut release = release(
+            "1.16.5",
+            None,
+            Some("ef4f57b922df243d0cef096efe808c72db042149"),
+        );
+        release.apply_log4shell_mitigation();
+        assert_eq!(
+            release.client_logging().map(|logging| &logging.file),
+            Some(&patched_config_1_12())
+        );
+    }
+
+    #[test]
+    fn jvm_flag() -> anyhow::Result<()> {
+        let mut release = crate::test::release_json("1.17.1");
+        release["arguments"] = json!({ "game": [], "jvm": ["-cp", "${classpath}"] });
+        let mut release: ReleaseData = serde_json::from_value(release)?;
+        release.libraries = self::release("1.17.1", Some("2.14.1"), None).libraries;
+
+        assert_eq!(
+            release.apply_log4shell_mitigation(),
+            Log4ShellStatus::Affected(Log4ShellMitigation::JvmFlag)
+        );
+        assert_eq!(
+            release.apply_log4shell_mitigation(),
+            Log4ShellStatus::Mitigated(Log4ShellMitigation::JvmFlag)
+        );
+        let jvm = &release.arguments.as_ref().unwrap().jvm;
+        assert_eq!(
+            jvm.iter()
+                .filter(|argument| **argument == Argument::from(FORMAT_MSG_NO_LOOKUPS))
+                .count(),
+            1
+        );
+        Ok(())
+    }
+}
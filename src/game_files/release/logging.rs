@@ -0,0 +1,181 @@
+use std::path::{Path, PathBuf};
+
+use tracing::debug;
+
+use crate::{
+    game_files::release::data::{Logging, ReleaseData},
+    http::IntoResult,
+    utils::{
+        hash::{check_file, verify_sha1, FileState},
+        write_atomic,
+    },
+    APIClient, Error,
+};
+
+/// The key of the client entry inside [ReleaseData::logging]
+pub static CLIENT_LOGGING: &str = "client";
+/// The directory inside of the assets directory the log configs are stored in
+pub static LOG_CONFIGS_DIR: &str = "log_configs";
+
+impl ReleaseData {
+    /// The logging configuration for the client.
+    /// Versions released before 1.7 do not have one
+    pub fn client_logging(&self) -> Option<&Logging> {
+        self.logging.get(CLIENT_LOGGING)
+    }
+    /// Downloads the client log config into `{assets_dir}/log_configs`.
+    ///
+    /// Returns the JVM argument to add to the launch command. Or None if the version has no logging config
+    pub async fn download_client_logging(
+        &self,
+        client: &APIClient,
+        assets_dir: impl AsRef<Path>,
+    ) -> Result<Option<String>, Error> {
+        match self.client_logging() {
+            Some(logging) => logging.download(client, assets_dir).await.map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Logging {
+    /// Where the log config is stored inside of `assets_dir`
+    pub fn config_path(&self, assets_dir: impl AsRef<Path>) -> PathBuf {
+        assets_dir
+            .as_ref()
+            .join(LOG_CONFIGS_DIR)
+            .join(&self.file.id)
+    }
+    /// The argument with `${path}` replaced by `config`
+    pub fn jvm_argument(&self, config: impl AsRef<Path>) -> String {
+        self.argument
+            .replace("${path}", &config.as_ref().to_string_lossy())
+    }
+    /// Downloads the log config into `{assets_dir}/log_configs` verifying the SHA-1.
+    /// If the file already exists with the correct SHA-1 it is not downloaded again.
+    ///
+    /// Returns the expanded JVM argument
+    pub async fn download(
+        &self,
+        client: &APIClient,
+        assets_dir: impl AsRef<Path>,
+    ) -> Result<String, Error> {
+        let location = self.config_path(assets_dir);
+        let state = check_file(&location, Some(self.file.size), Some(&self.file.sha1)).await?;
+        if state == FileState::Valid {
+            debug!(?location, "Log config already downloaded");
+            return Ok(self.jvm_argument(&location));
+        }
+        let url = client.game_files.redirect(&self.file.url)?;
+        let bytes = client
+            .http_client
+            .get(url)
+            .send()
+            .await?
+            .into_result::<Error>()
+            .await?
+            .bytes()
+            .await?;
+        verify_sha1(&self.file.url, &bytes, &self.file.sha1)?;
+        write_atomic(&location, &bytes).await?;
+        Ok(self.jvm_argument(&location))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{game_files::release::ReleaseData, test::TestServer, utils::hash::sha1_hex, Error};
+
+    fn logging_json(sha1: &str) -> serde_json::Value {
+        logging_json_at(sha1, "http://127.0.0.1:9/client-1.12.xml")
+    }
+    fn logging_json_at(sha1: &str, url: &str) -> serde_json::Value {
+        json!({
+            "client": {
+                "argument": "-Dlog4j.configurationFile=${path}",
+                "file": {
+                    "id": "client-1.12.xml",
+                    "sha1": sha1,
+                    "size": 6,
+                    "url": url
+                },
+                "type": "log4j2-xml"
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn no_logging_entry() -> anyhow::Result<()> {
+        let client = crate::test::setup();
+        let dir = tempfile::tempdir()?;
+        let release: ReleaseData = serde_json::from_value(crate::test::release_json("1.6.4"))?;
+        assert!(release.client_logging().is_none());
+        assert_eq!(
+            release.download_client_logging(&client, dir.path()).await?,
+            None
+        );
+        assert!(!dir.path().join("log_configs").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn existing_log_config() -> anyhow::Result<()> {
+        let client = crate::test::setup();
+        let dir = tempfile::tempdir()?;
+        let mut release = crate::test::release_json("1.12.2");
+        release["logging"] = logging_json(&sha1_hex(b"<xml/>"));
+        let release: ReleaseData = serde_json::from_value(release)?;
+
+        let location = dir.path().join("log_configs").join("client-1.12.xml");
+        std::fs::create_dir_all(location.parent().unwrap())?;
+        std::fs::write(&location, b"<xml/>")?;
+        let argument = release.download_client_logging(&client, dir.path()).await?;
+        assert_eq!(
+            argument,
+            Some(format!("-Dlog4j.configurationFile={}", location.display()))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_log_config() -> anyhow::Result<()> {
+        let server = TestServer::start().await?;
+        server.insert("/client-1.12.xml", b"<xml/>".to_vec());
+        let client = server.client();
+        let dir = tempfile::tempdir()?;
+        let mut release = crate::test::release_json("1.12.2");
+        release["logging"] = logging_json_at(&sha1_hex(b"<xml/>"), &server.url("/client-1.12.xml"));
+        let release: ReleaseData = serde_json::from_value(release)?;
+
+        let location = dir.path().join("log_configs").join("client-1.12.xml");
+        let argument = release.download_client_logging(&client, dir.path()).await?;
+        assert_eq!(
+            argument,
+            Some(format!("-Dlog4j.configurationFile={}", location.display()))
+        );
+        assert_eq!(std::fs::read(&location)?, b"<xml/>");
+        assert_eq!(std::fs::read_dir(location.parent().unwrap())?.count(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn log_config_hash_mismatch() -> anyhow::Result<()> {
+        let server = TestServer::start().await?;
+        server.insert("/client-1.12.xml", b"<tampered/>".to_vec());
+        let client = server.client();
+        let dir = tempfile::tempdir()?;
+        let mut release = crate::test::release_json("1.12.2");
+        release["logging"] = logging_json_at(&sha1_hex(b"<xml/>"), &server.url("/client-1.12.xml"));
+        let release: ReleaseData = serde_json::from_value(release)?;
+
+        let result = release.download_client_logging(&client, dir.path()).await;
+        assert!(matches!(result, Err(Error::HashMismatch(_))));
+        assert!(!dir
+            .path()
+            .join("log_configs")
+            .join("client-1.12.xml")
+            .exists());
+        Ok(())
+    }
+}
@@ -1,9 +1,19 @@
 pub mod argument;
 pub mod data;
+pub mod diff;
 pub mod library;
+pub mod log4shell;
+pub mod logging;
+pub mod patch;
+pub mod platform;
 pub mod rule;
 
 pub use argument::*;
 pub use data::*;
+pub use diff::*;
 pub use library::*;
+pub use log4shell::{Log4ShellMitigation, Log4ShellStatus};
+pub use logging::*;
+pub use patch::*;
+pub use platform::*;
 pub use rule::*;
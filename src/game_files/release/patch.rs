@@ -0,0 +1,192 @@
+//! Substitutes libraries for platforms the release does not support. Such as LWJGL natives for `linux` `arm64`
+//!
+//! Patches are read from a JSON file
+//! ```json
+//! {
+//!   "patches": [{
+//!     "library": "org.lwjgl.lwjgl:lwjgl-platform:*",
+//!     "rules": [{ "action": "allow", "os": { "name": "linux", "arch": "arm64" } }],
+//!     "replace": [{
+//!       "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+//!       "downloads": { "classifiers": { "natives-linux-arm64": { "path": "...", "sha1": "...", "size": 0, "url": "..." } } },
+//!       "natives": { "linux": "natives-linux-arm64" }
+//!     }]
+//!   }]
+//! }
+//! ```
+//! The patched [ReleaseData::libraries] are used by everything reading the release. Such as [Installation::classpath](crate::game_files::installation::Installation::classpath)
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::read;
+
+use crate::{
+    game_files::release::{
+        data::ReleaseData,
+        library::Library,
+        platform::{rules_allow, Platform},
+        rule::Rule,
+    },
+    Error,
+};
+
+/// A declarative list of library substitutions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryPatches {
+    pub patches: Vec<LibraryPatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryPatch {
+    /// `group:artifact[:version[:classifier]]`. `*` matches any value. Omitted parts match anything
+    pub library: String,
+    /// Evaluated against the target platform like the rules of a [Library]. The patch applies everywhere if empty
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Inserted once in place of the first matched library. Every matched library is removed
+    #[serde(default)]
+    pub replace: Vec<Library>,
+}
+impl LibraryPatch {
+    /// If the patch applies to the library on the platform
+    pub fn matches(&self, library: &Library, platform: &Platform) -> bool {
+        (self.rules.is_empty() || rules_allow(&self.rules, platform))
+            && coordinate_matches(&self.library, &library.name)
+    }
+}
+
+/// A library replaced by [LibraryPatches::apply]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PatchedLibrary {
+    pub original: Library,
+    /// The index of the patch within [LibraryPatches::patches]
+    pub patch: usize,
+}
+
+impl LibraryPatches {
+    /// Reads a patch file
+    pub async fn read(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let bytes = read(path).await?;
+        serde_json::from_slice(&bytes).map_err(Error::from)
+    }
+    /// Replaces the libraries matched on the platform. The first matching patch is used for each library.
+    ///
+    /// The replacements of a patch are inserted once where its first match was. Returns the replaced libraries
+    pub fn apply(&self, release: &mut ReleaseData, platform: &Platform) -> Vec<PatchedLibrary> {
+        let mut inserted = vec![false; self.patches.len()];
+        let mut patched = Vec::new();
+        let mut libraries = Vec::with_capacity(release.libraries.len());
+        for library in release.libraries.drain(..) {
+            let Some(index) = self
+                .patches
+                .iter()
+                .position(|patch| patch.matches(&library, platform))
+            else {
+                libraries.push(library);
+                continue;
+            };
+            if !inserted[index] {
+                inserted[index] = true;
+                libraries.extend(self.patches[index].replace.iter().cloned());
+            }
+            patched.push(PatchedLibrary {
+                original: library,
+                patch: index,
+            });
+        }
+        release.libraries = libraries;
+        patched
+    }
+}
+
+fn coordinate_matches(pattern: &str, name: &str) -> bool {
+    let mut name = name.split(':');
+    pattern.split(':').all(|expected| {
+        name.next()
+            .is_some_and(|part| expected == "*" || expected == part)
+    })
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::game_files::{
+        installation::Installation,
+        release::{
+            data::ReleaseData,
+            patch::{coordinate_matches, LibraryPatches},
+            platform::Platform,
+        },
+    };
+
+    #[test]
+    fn coordinates() {
+        let name = "org.lwjgl:lwjgl:3.3.1:natives-linux";
+        assert!(coordinate_matches("org.lwjgl:lwjgl", name));
+        assert!(coordinate_matches("org.lwjgl:lwjgl:*:natives-linux", name));
+        assert!(!coordinate_matches("org.lwjgl:lwjgl:3.2.2", name));
+        assert!(!coordinate_matches("org.lwjgl:lwjgl-glfw", name));
+        assert!(!coordinate_matches(
+            "org.lwjgl:lwjgl:3.2.2:natives-linux",
+            "org.lwjgl:lwjgl:3.2.2"
+        ));
+    }
+
+    #[test]
+    fn apply() -> anyhow::Result<()> {
+        let artifact = |path: &str| json!({ "path": path, "sha1": "", "size": 0, "url": "" });
+        let mut release = crate::test::release_json("1.16.5");
+        release["libraries"] = json!([
+            { "name": "com.mojang:brigadier:1.0.17", "downloads": { "artifact": artifact("brigadier.jar") } },
+            { "name": "org.lwjgl:lwjgl:3.2.2", "downloads": { "artifact": artifact("lwjgl-3.2.2.jar") } },
+            {
+                "name": "org.lwjgl:lwjgl:3.2.2",
+                "downloads": { "classifiers": { "natives-linux": artifact("lwjgl-3.2.2-natives-linux.jar") } },
+                "natives": { "linux": "natives-linux" }
+            }
+        ]);
+        let release: ReleaseData = serde_json::from_value(release)?;
+        let patches: LibraryPatches = serde_json::from_value(json!({
+            "patches": [{
+                "library": "org.lwjgl:lwjgl",
+                "rules": [{ "action": "allow", "os": { "name": "linux", "arch": "arm64" } }],
+                "replace": [
+                    { "name": "org.lwjgl:lwjgl:3.3.1", "downloads": { "artifact": artifact("lwjgl-3.3.1.jar") } },
+                    { "name": "org.lwjgl:lwjgl:3.3.1:natives-linux-arm64", "downloads": { "artifact": artifact("lwjgl-3.3.1-natives-linux-arm64.jar") } }
+                ]
+            }]
+        }))?;
+
+        let mut x86 = release.clone();
+        assert!(patches
+            .apply(&mut x86, &Platform::new("linux", "x86_64"))
+            .is_empty());
+        assert_eq!(x86.libraries, release.libraries);
+
+        let installation = Installation::new("/games");
+        let linux_arm64 = Platform::new("linux", "arm64");
+        assert_eq!(installation.natives(&release, &linux_arm64).len(), 1);
+        let mut arm = release.clone();
+        let patched = patches.apply(&mut arm, &linux_arm64);
+        assert_eq!(patched.len(), 2);
+        let names: Vec<&str> = arm
+            .libraries
+            .iter()
+            .map(|library| library.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "com.mojang:brigadier:1.0.17",
+                "org.lwjgl:lwjgl:3.3.1",
+                "org.lwjgl:lwjgl:3.3.1:natives-linux-arm64"
+            ]
+        );
+        assert!(installation.natives(&arm, &linux_arm64).is_empty());
+        let classpath = installation.classpath(&arm, &linux_arm64);
+        assert_eq!(classpath.len(), 4);
+        assert!(classpath[2].ends_with("lwjgl-3.3.1-natives-linux-arm64.jar"));
+        assert!(classpath[3].ends_with("versions/1.16.5/1.16.5.jar"));
+        Ok(())
+    }
+}
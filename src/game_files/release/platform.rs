@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game_files::release::{
+    library::{Artifact, Library},
+    rule::{Rule, RuleOS, RuleRequirement, RuleType},
+};
+
+/// An operating system and architecture as named by the release rules
+///
+/// `os` is one of `windows`, `osx` or `linux`. `arch` is `x86`, `x86_64` or `arm64`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Platform {
+    pub os: String,
+    pub arch: String,
+    /// Matched against `os.version` rules. Rules requiring a version are ignored if None
+    #[serde(default)]
+    pub os_version: Option<String>,
+}
+impl Platform {
+    pub fn new(os: impl Into<String>, arch: impl Into<String>) -> Self {
+        Self {
+            os: os.into(),
+            arch: arch.into(),
+            os_version: None,
+        }
+    }
+    /// The platform this is running on
+    pub fn current() -> Self {
+        let os = match std::env::consts::OS {
+            "macos" => "osx",
+            os => os,
+        };
+        let arch = match std::env::consts::ARCH {
+            "aarch64" => "arm64",
+            arch => arch,
+        };
+        Self::new(os, arch)
+    }
+    /// The value of `${arch}` within the native classifiers
+    pub fn arch_bits(&self) -> &'static str {
+        match self.arch.as_str() {
+            "x86" | "arm" => "32",
+            _ => "64",
+        }
+    }
+}
+
+impl Rule {
+    /// If the requirements of the rule are met on the platform. Feature requirements are never met
+    pub fn matches(&self, platform: &Platform) -> bool {
+        match &self.requirement {
+            RuleRequirement::Default => true,
+            RuleRequirement::Features(_) => false,
+            RuleRequirement::OS(os) => os.iter().all(|requirement| match requirement {
+                RuleOS::Name(name) => *name == platform.os,
+                RuleOS::Arch(arch) => *arch == platform.arch,
+                // Only the `^prefix` form Mojang uses is supported
+                RuleOS::Version(pattern) => platform.os_version.as_ref().is_none_or(|version| {
+                    version.starts_with(&pattern.trim_start_matches('^').replace("\\.", "."))
+                }),
+                RuleOS::Other { .. } => true,
+            }),
+        }
+    }
+}
+
+/// The last rule matching the platform decides. Nothing is allowed if no rule matches
+pub(crate) fn rules_allow(rules: &[Rule], platform: &Platform) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(platform))
+        .is_some_and(|rule| rule.action == RuleType::Allow)
+}
+
+impl Library {
+    /// If the library is used on the platform. The last matching rule decides
+    pub fn applies_to(&self, platform: &Platform) -> bool {
+        self.rules
+            .as_ref()
+            .is_none_or(|rules| rules_allow(rules, platform))
+    }
+    /// The classifier holding the natives for the platform. Such as `natives-windows-64`
+    pub fn native_classifier(&self, platform: &Platform) -> Option<String> {
+        let classifier = self.natives.as_ref()?.get(&platform.os)?;
+        Some(classifier.replace("${arch}", platform.arch_bits()))
+    }
+    /// The artifact holding the natives for the platform
+    pub fn native_artifact(&self, platform: &Platform) -> Option<&Artifact> {
+        let classifier = self.native_classifier(platform)?;
+        self.downloads.classifiers.get(&classifier)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::game_files::release::{library::Library, platform::Platform};
+
+    #[test]
+    fn applies_to() -> anyhow::Result<()> {
+        let library: Library = serde_json::from_value(json!({
+            "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209",
+            "downloads": {
+                "classifiers": {
+                    "natives-linux": { "path": "linux.jar", "sha1": "", "size": 0, "url": "" },
+                    "natives-windows-64": { "path": "windows.jar", "sha1": "", "size": 0, "url": "" }
+                }
+            },
+            "natives": { "linux": "natives-linux", "windows": "natives-windows-${arch}" },
+            "rules": [
+                { "action": "allow" },
+                { "action": "disallow", "os": { "name": "osx" } }
+            ]
+        }))?;
+        let linux = Platform::new("linux", "x86_64");
+        let windows = Platform::new("windows", "x86_64");
+        assert!(library.applies_to(&linux));
+        assert!(!library.applies_to(&Platform::new("osx", "arm64")));
+        assert_eq!(library.native_artifact(&linux).unwrap().path, "linux.jar");
+        assert_eq!(
+            library.native_classifier(&Platform::new("windows", "x86")),
+            Some("natives-windows-32".to_owned())
+        );
+        assert_eq!(
+            library.native_artifact(&windows).unwrap().path,
+            "windows.jar"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+//! Pulls the [ReleaseData] of many versions at once.
+//!
+//! Requests are capped by [FetchOptions::concurrency] and spaced by [FetchOptions::request_interval] to stay below the rate limits of the launcher meta
+use std::{collections::HashMap, time::Duration};
+
+use tokio::{
+    sync::mpsc::{channel, Receiver},
+    task::{Id, JoinError, JoinSet},
+    time::{interval, MissedTickBehavior},
+};
+use tracing::warn;
+
+use crate::{
+    game_files::{
+        release::data::ReleaseData,
+        version_manifest::{Version, VersionManifest},
+    },
+    APIClient, Error,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchOptions {
+    /// The number of requests in flight. At least one
+    pub concurrency: usize,
+    /// The minimum time between starting two requests. Zero does not limit the rate
+    pub request_interval: Duration,
+}
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            request_interval: Duration::from_millis(50),
+        }
+    }
+}
+
+/// The result for a single version
+#[derive(Debug)]
+pub struct FetchedRelease {
+    pub version: Version,
+    pub result: Result<ReleaseData, Error>,
+}
+
+/// The result of [VersionManifest::fetch_releases]. In the order the versions were given
+#[derive(Debug, Default)]
+pub struct FetchedReleases {
+    pub releases: Vec<ReleaseData>,
+    /// The versions that could not be pulled or parsed
+    pub errors: Vec<(Version, Error)>,
+}
+
+impl VersionManifest {
+    /// Pulls the release of every version in the manifest. See [fetch_releases]
+    pub async fn fetch_releases(
+        &self,
+        client: &APIClient,
+        options: FetchOptions,
+    ) -> FetchedReleases {
+        fetch_releases(client, self.versions.clone(), options).await
+    }
+    /// Pulls the release of every version in the manifest. See [stream_releases]
+    pub fn stream_releases(
+        &self,
+        client: &APIClient,
+        options: FetchOptions,
+    ) -> Receiver<FetchedRelease> {
+        stream_releases(client, self.versions.clone(), options)
+    }
+}
+
+/// Pulls the releases and waits for all of them. A failed version does not stop the others
+pub async fn fetch_releases(
+    client: &APIClient,
+    versions: Vec<Version>,
+    options: FetchOptions,
+) -> FetchedReleases {
+    let order: Vec<String> = versions.iter().map(|version| version.id.clone()).collect();
+    let mut fetched = Vec::with_capacity(versions.len());
+    let mut results = stream_releases(client, versions, options);
+    while let Some(release) = results.recv().await {
+        fetched.push(release);
+    }
+    fetched.sort_by_key(|release| order.iter().position(|id| *id == release.version.id));
+
+    let mut releases = FetchedReleases::default();
+    for FetchedRelease { version, result } in fetched {
+        match result {
+            Ok(release) => releases.releases.push(release),
+            Err(err) => releases.errors.push((version, err)),
+        }
+    }
+    releases
+}
+
+/// Pulls the releases on a new task. Results are sent as they arrive. Not in the order the versions were given.
+///
+/// The task stops once the receiver is dropped
+/// ```no_run
+/// # async fn report(client: minecraft_rs::APIClient) -> Result<(), minecraft_rs::Error> {
+/// use minecraft_rs::game_files::release_fetch::FetchOptions;
+///
+/// let manifest = client.version_manifest().await?;
+/// let mut results = manifest.stream_releases(&client, FetchOptions::default());
+/// while let Some(fetched) = results.recv().await {
+///     match fetched.result {
+///         Ok(release) => println!("{} uses Java {}", release.id, release.java_version.major_version),
+///         Err(err) => eprintln!("{} failed {err:?}", fetched.version.id),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn stream_releases(
+    client: &APIClient,
+    versions: Vec<Version>,
+    options: FetchOptions,
+) -> Receiver<FetchedRelease> {
+    let concurrency = options.concurrency.max(1);
+    let (sender, receiver) = channel(concurrency);
+    let client = client.clone();
+    tokio::spawn(async move {
+        let mut ticks = (!options.request_interval.is_zero()).then(|| {
+            let mut ticks = interval(options.request_interval);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            ticks
+        });
+        let mut requests = JoinSet::new();
+        // Kept outside the tasks so a panicked task is still reported for its version
+        let mut pending = HashMap::new();
+        for version in versions {
+            if requests.len() >= concurrency {
+                if let Some(joined) = requests.join_next_with_id().await {
+                    if sender.send(finished(joined, &mut pending)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            if sender.is_closed() {
+                return;
+            }
+            if let Some(ticks) = &mut ticks {
+                ticks.tick().await;
+            }
+            let client = client.clone();
+            let request = version.clone();
+            let task = requests.spawn(async move { request.get_release(&client).await });
+            pending.insert(task.id(), version);
+        }
+        while let Some(joined) = requests.join_next_with_id().await {
+            if sender.send(finished(joined, &mut pending)).await.is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+fn finished(
+    joined: Result<(Id, Result<ReleaseData, Error>), JoinError>,
+    pending: &mut HashMap<Id, Version>,
+) -> FetchedRelease {
+    let (id, result) = match joined {
+        Ok((id, result)) => (id, result),
+        Err(err) => (err.id(), Err(Error::Custom(err.to_string()))),
+    };
+    let version = pending
+        .remove(&id)
+        .expect("Every task is spawned with a pending version");
+    if let Err(err) = &result {
+        warn!(id = version.id, ?err, "Could not pull the release");
+    }
+    FetchedRelease { version, result }
+}
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use crate::{game_files::release_fetch::FetchOptions, utils::hash::sha1_hex, Error};
+
+    #[tokio::test]
+    async fn fetch_releases() -> anyhow::Result<()> {
+        let server = crate::test::TestServer::start().await?;
+        let client = server.client();
+        let mut manifest = crate::test::version_manifest();
+        manifest.versions.truncate(4);
+        for version in &mut manifest.versions {
+            let body = serde_json::to_vec(&crate::test::release_json(&version.id))?;
+            version.sha1 = sha1_hex(&body);
+            server.insert(
+                &format!(
+                    "/launcher_meta/v1/packages/0000000000000000000000000000000000000000/{}.json",
+                    version.id
+                ),
+                body,
+            );
+        }
+        manifest.versions[1].sha1 = sha1_hex(b"Republished");
+
+        let options = FetchOptions {
+            concurrency: 2,
+            request_interval: Duration::from_millis(20),
+        };
+        let start = Instant::now();
+        let fetched = manifest.fetch_releases(&client, options).await;
+        assert!(start.elapsed() >= Duration::from_millis(60));
+        let ids: Vec<&str> = fetched
+            .releases
+            .iter()
+            .map(|release| release.id.as_str())
+            .collect();
+        assert_eq!(ids, ["1.20.5", "24w14a", "24w14potato"]);
+        assert_eq!(fetched.errors.len(), 1);
+        assert_eq!(fetched.errors[0].0.id, "1.20.5-rc1");
+        assert!(matches!(fetched.errors[0].1, Error::HashMismatch(_)));
+
+        let mut results = manifest.stream_releases(&client, FetchOptions::default());
+        let mut received = 0;
+        while let Some(release) = results.recv().await {
+            assert_eq!(release.result.is_ok(), release.version.id != "1.20.5-rc1");
+            received += 1;
+        }
+        assert_eq!(received, 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+//! Maps the absolute Mojang URLs found in the metadata onto the bases configured in [GameFilesAPIBuilder].
+//!
+//! Applied by [APIClient::version_manifest](crate::APIClient::version_manifest) and [Version::get_release](crate::game_files::version_manifest::Version::get_release).
+//! Bases that still point at Mojang are left alone.
+use reqwest::Url;
+
+use crate::game_files::{
+    join_url,
+    release::data::{Download, ReleaseData},
+    version_manifest::VersionManifest,
+    Endpoint, GameFilesAPIBuilder,
+};
+
+impl GameFilesAPIBuilder {
+    /// Maps a URL on a known Mojang host onto the configured base of its endpoint.
+    ///
+    /// None if the host is unknown or the configured base is a Mojang host
+    pub fn rewrite_url(&self, url: &str) -> Option<String> {
+        let url = Url::parse(url).ok()?;
+        let endpoint = Endpoint::from_mojang_host(url.host_str()?)?;
+        let base = self.base(endpoint);
+        if self.is_mojang_base(endpoint) {
+            return None;
+        }
+        let mut rewritten = join_url(base, url.path()).ok()?;
+        rewritten.set_query(url.query());
+        Some(rewritten.into())
+    }
+    /// Parses the URL after rewriting it onto the configured base
+    pub(crate) fn redirect(&self, url: &str) -> Result<Url, url::ParseError> {
+        match self.rewrite_url(url) {
+            Some(rewritten) => Url::parse(&rewritten),
+            None => Url::parse(url),
+        }
+    }
+    /// The URLs to try in order for a URL found in the metadata. Including the mirrors of its endpoint.
+    ///
+    /// URLs on a Mojang host are tried as they are while the base is still Mojang's. The launcher meta base does not serve the files of `piston-data`
+    pub(crate) fn urls_for(&self, url: &str) -> Result<Vec<Url>, url::ParseError> {
+        let parsed = Url::parse(url)?;
+        let Some((endpoint, path)) = self.endpoint_path(&parsed) else {
+            return Ok(vec![parsed]);
+        };
+        let mut urls = self.create_urls(endpoint, &path)?;
+        let mojang_host = parsed.host_str().and_then(Endpoint::from_mojang_host) == Some(endpoint);
+        if mojang_host && self.is_mojang_base(endpoint) {
+            urls[0] = parsed;
+        }
+        Ok(urls)
+    }
+    /// If the configured base of the endpoint is still a Mojang host
+    fn is_mojang_base(&self, endpoint: Endpoint) -> bool {
+        Url::parse(self.base(endpoint))
+            .ok()
+            .and_then(|base| base.host_str().and_then(Endpoint::from_mojang_host))
+            == Some(endpoint)
+    }
+    /// Rewrites the URL in place. Returns true if it was changed
+    pub(crate) fn rewrite_in_place(&self, url: &mut String) -> bool {
+        match self.rewrite_url(url) {
+            Some(rewritten) => {
+                *url = rewritten;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl ReleaseData {
+    /// Rewrites the downloads, asset index, library artifacts and log configs onto the configured bases.
+    ///
+    /// Returns the number of URLs rewritten
+    pub fn rewrite_urls(&mut self, game_files: &GameFilesAPIBuilder) -> usize {
+        let downloads = &mut self.downloads;
+        let downloads = std::iter::once(&mut downloads.client)
+            .chain(downloads.server.as_mut())
+            .chain(downloads.server_mapping.as_mut())
+            .chain(downloads.client_mapping.as_mut())
+            .map(|download: &mut Download| &mut download.url);
+        let artifacts = self.libraries.iter_mut().flat_map(|library| {
+            let downloads = &mut library.downloads;
+            downloads
+                .artifact
+                .iter_mut()
+                .chain(downloads.classifiers.values_mut())
+                .map(|artifact| &mut artifact.url)
+        });
+        let logging = self
+            .logging
+            .values_mut()
+            .map(|logging| &mut logging.file.url);
+        downloads
+            .chain(std::iter::once(&mut self.asset_index.url))
+            .chain(artifacts)
+            .chain(logging)
+            .map(|url| game_files.rewrite_in_place(url))
+            .filter(|rewritten| *rewritten)
+            .count()
+    }
+}
+
+impl VersionManifest {
+    /// Rewrites the URL of every version onto the configured launcher meta.
+    ///
+    /// The SHA-1 of the versions still match as the release JSONs are not modified
+    pub fn rewrite_urls(&mut self, game_files: &GameFilesAPIBuilder) -> usize {
+        self.versions
+            .iter_mut()
+            .map(|version| game_files.rewrite_in_place(&mut version.url))
+            .filter(|rewritten| *rewritten)
+            .count()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use serde_json::json;
+
+    use crate::game_files::{release::data::ReleaseData, Endpoint, GameFilesAPIBuilder};
+
+    fn mirror() -> GameFilesAPIBuilder {
+        GameFilesAPIBuilder::from_mirror("https://mirror.example/minecraft")
+    }
+
+    #[test]
+    fn rewrite_url() {
+        let mirror = mirror();
+        assert_eq!(
+            mirror
+                .rewrite_url("https://piston-data.mojang.com/v1/objects/abc/client.jar")
+                .as_deref(),
+            Some("https://mirror.example/minecraft/launcher_meta/v1/objects/abc/client.jar")
+        );
+        assert_eq!(
+            mirror.rewrite_url("https://maven.example/com/example/lib.jar"),
+            None
+        );
+        let default = GameFilesAPIBuilder::default();
+        assert_eq!(
+            default.rewrite_url("https://piston-data.mojang.com/v1/objects/abc/client.jar"),
+            None
+        );
+        let libraries_only = GameFilesAPIBuilder {
+            library_base: Cow::Borrowed("https://maven.example/mojang"),
+            ..Default::default()
+        };
+        assert_eq!(
+            libraries_only
+                .rewrite_url("https://libraries.minecraft.net/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar")
+                .as_deref(),
+            Some("https://maven.example/mojang/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar")
+        );
+        assert_eq!(
+            libraries_only.base(Endpoint::LauncherMeta),
+            "https://piston-meta.mojang.com"
+        );
+    }
+
+    #[test]
+    fn urls_for() -> anyhow::Result<()> {
+        let client_jar = "https://piston-data.mojang.com/v1/objects/abc/client.jar";
+        let urls = GameFilesAPIBuilder::default().urls_for(client_jar)?;
+        assert_eq!(urls.len(), 1);
+        assert_eq!(urls[0].as_str(), client_jar);
+
+        let with_mirror = GameFilesAPIBuilder::default()
+            .with_mirror(Endpoint::LauncherMeta, "https://mirror.example/meta");
+        let urls = with_mirror.urls_for(client_jar)?;
+        assert_eq!(urls[0].as_str(), client_jar);
+        assert_eq!(
+            urls[1].as_str(),
+            "https://mirror.example/meta/v1/objects/abc/client.jar"
+        );
+
+        let urls = mirror().urls_for(client_jar)?;
+        assert_eq!(
+            urls[0].as_str(),
+            "https://mirror.example/minecraft/launcher_meta/v1/objects/abc/client.jar"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_release() -> anyhow::Result<()> {
+        let mut release = crate::test::release_json("1.20.4");
+        release["libraries"] = json!([{
+            "name": "com.mojang:brigadier:1.0.18",
+            "downloads": {
+                "artifact": {
+                    "path": "com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar",
+                    "sha1": "c1ef1234282716483c92183f49bef47b1a89bfa9",
+                    "size": 77116,
+                    "url": "https://libraries.minecraft.net/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar"
+                }
+            }
+        }]);
+        let mut release: ReleaseData = serde_json::from_value(release)?;
+        release.logging.insert(
+            "client".to_owned(),
+            serde_json::from_value(json!({
+                "argument": "-Dlog4j.configurationFile=${path}",
+                "file": crate::game_files::release::log4shell::patched_config_1_12(),
+                "type": "log4j2-xml"
+            }))?,
+        );
+
+        assert_eq!(release.rewrite_urls(&mirror()), 4);
+        assert!(release
+            .downloads
+            .client
+            .url
+            .starts_with("https://mirror.example/minecraft/launcher_meta/"));
+        assert_eq!(
+            release.libraries[0].downloads.artifact.as_ref().unwrap().url,
+            "https://mirror.example/minecraft/libraries/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar"
+        );
+        assert!(release
+            .client_logging()
+            .unwrap()
+            .file
+            .url
+            .starts_with("https://mirror.example/"));
+        assert_eq!(release.rewrite_urls(&mirror()), 0);
+
+        let mut manifest = crate::test::version_manifest();
+        assert_eq!(manifest.rewrite_urls(&mirror()), manifest.versions.len());
+        Ok(())
+    }
+}
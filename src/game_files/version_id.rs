@@ -0,0 +1,415 @@
+use std::{
+    cmp::Ordering,
+    convert::Infallible,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+/// A release number such as `1.20.6`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReleaseNumber {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+impl ReleaseNumber {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+impl FromStr for ReleaseNumber {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('.');
+        let mut next = |required: bool| match parts.next() {
+            Some(part) if !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()) => {
+                part.parse().map_err(|_| ())
+            }
+            None if !required => Ok(0),
+            _ => Err(()),
+        };
+        let release = ReleaseNumber::new(next(true)?, next(true)?, next(false)?);
+        if parts.next().is_some() {
+            return Err(());
+        }
+        Ok(release)
+    }
+}
+impl Display for ReleaseNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.patch == 0 {
+            write!(f, "{}.{}", self.major, self.minor)
+        } else {
+            write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        }
+    }
+}
+
+/// A parsed [Version::id](crate::game_files::version_manifest::Version::id)
+///
+/// Ordering is only defined when the ids alone can decide it. A weekly snapshot can not be compared to a release without knowing when they were released.
+/// Use [VersionManifest::compare_versions](crate::game_files::version_manifest::VersionManifest::compare_versions) to fall back to the release time.
+///
+/// Display produces the modern format. `1.14 Pre-Release 1` is displayed as `1.14-pre1`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VersionId {
+    /// `1.20.6`
+    Release(ReleaseNumber),
+    /// `1.20.5-pre1` or `1.14 Pre-Release 1`
+    PreRelease { release: ReleaseNumber, number: u32 },
+    /// `1.20.5-rc1`
+    ReleaseCandidate { release: ReleaseNumber, number: u32 },
+    /// `26.1-snapshot-1`
+    ReleaseSnapshot { release: ReleaseNumber, number: u32 },
+    /// Weekly snapshots `24w14a`
+    Snapshot {
+        year: u32,
+        week: u32,
+        revision: char,
+    },
+    /// `b1.7.3` or `b1.1_02`
+    Beta {
+        release: ReleaseNumber,
+        suffix: String,
+    },
+    /// `a1.0.4` or `a1.1.2_01`
+    Alpha {
+        release: ReleaseNumber,
+        suffix: String,
+    },
+    /// April Fools and other ids without a known format. `3D Shareware v1.34`, `24w14potato` or `rd-132211`
+    Special(String),
+}
+
+impl VersionId {
+    pub fn parse(id: &str) -> Self {
+        parse_modern(id)
+            .or_else(|| parse_snapshot(id))
+            .or_else(|| parse_old(id))
+            .unwrap_or_else(|| VersionId::Special(id.to_owned()))
+    }
+    /// The release this version is or is leading up to. Weekly snapshots do not contain their release target
+    pub fn release_target(&self) -> Option<ReleaseNumber> {
+        match self {
+            VersionId::Release(release)
+            | VersionId::PreRelease { release, .. }
+            | VersionId::ReleaseCandidate { release, .. }
+            | VersionId::ReleaseSnapshot { release, .. } => Some(*release),
+            _ => None,
+        }
+    }
+    pub fn is_release(&self) -> bool {
+        matches!(self, VersionId::Release(_))
+    }
+    pub fn is_pre_release(&self) -> bool {
+        matches!(self, VersionId::PreRelease { .. })
+    }
+    pub fn is_release_candidate(&self) -> bool {
+        matches!(self, VersionId::ReleaseCandidate { .. })
+    }
+    /// Weekly snapshots and release snapshots
+    pub fn is_snapshot(&self) -> bool {
+        matches!(
+            self,
+            VersionId::Snapshot { .. } | VersionId::ReleaseSnapshot { .. }
+        )
+    }
+    pub fn is_special(&self) -> bool {
+        matches!(self, VersionId::Special(_))
+    }
+
+    /// Alpha < Beta < everything since 1.0
+    fn era(&self) -> Option<u8> {
+        match self {
+            VersionId::Alpha { .. } => Some(0),
+            VersionId::Beta { .. } => Some(1),
+            VersionId::Special(_) => None,
+            _ => Some(2),
+        }
+    }
+    /// The position of a version within the cycle of its release target
+    fn stage(&self) -> Option<(u8, u32)> {
+        match self {
+            VersionId::ReleaseSnapshot { number, .. } => Some((0, *number)),
+            VersionId::PreRelease { number, .. } => Some((1, *number)),
+            VersionId::ReleaseCandidate { number, .. } => Some((2, *number)),
+            VersionId::Release(_) => Some((3, 0)),
+            _ => None,
+        }
+    }
+}
+
+impl PartialOrd for VersionId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        let era = self.era()?.cmp(&other.era()?);
+        if era != Ordering::Equal {
+            return Some(era);
+        }
+        match (self, other) {
+            (
+                VersionId::Snapshot {
+                    year,
+                    week,
+                    revision,
+                },
+                VersionId::Snapshot {
+                    year: other_year,
+                    week: other_week,
+                    revision: other_revision,
+                },
+            ) => Some((year, week, revision).cmp(&(other_year, other_week, other_revision))),
+            (
+                VersionId::Alpha { release, suffix },
+                VersionId::Alpha {
+                    release: other_release,
+                    suffix: other_suffix,
+                },
+            )
+            | (
+                VersionId::Beta { release, suffix },
+                VersionId::Beta {
+                    release: other_release,
+                    suffix: other_suffix,
+                },
+            ) => match release.cmp(other_release) {
+                Ordering::Equal if suffix.is_empty() => Some(Ordering::Less),
+                Ordering::Equal if other_suffix.is_empty() => Some(Ordering::Greater),
+                // `b1.3b` and `b1.3_01` can not be ordered by name
+                Ordering::Equal => None,
+                ordering => Some(ordering),
+            },
+            _ => {
+                let release = self.release_target()?.cmp(&other.release_target()?);
+                Some(release.then(self.stage()?.cmp(&other.stage()?)))
+            }
+        }
+    }
+}
+
+impl From<&str> for VersionId {
+    fn from(value: &str) -> Self {
+        VersionId::parse(value)
+    }
+}
+impl FromStr for VersionId {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(VersionId::parse(s))
+    }
+}
+impl Display for VersionId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionId::Release(release) => write!(f, "{release}"),
+            VersionId::PreRelease { release, number } => write!(f, "{release}-pre{number}"),
+            VersionId::ReleaseCandidate { release, number } => write!(f, "{release}-rc{number}"),
+            VersionId::ReleaseSnapshot { release, number } => {
+                write!(f, "{release}-snapshot-{number}")
+            }
+            VersionId::Snapshot {
+                year,
+                week,
+                revision,
+            } => write!(f, "{year:02}w{week:02}{revision}"),
+            VersionId::Beta { release, suffix } => write!(f, "b{release}{suffix}"),
+            VersionId::Alpha { release, suffix } => write!(f, "a{release}{suffix}"),
+            VersionId::Special(id) => f.write_str(id),
+        }
+    }
+}
+
+fn parse_number(value: &str) -> Option<u32> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+/// Releases, pre-releases, release candidates and release snapshots
+fn parse_modern(id: &str) -> Option<VersionId> {
+    if let Some((release, number)) = id.split_once(" Pre-Release ") {
+        return Some(VersionId::PreRelease {
+            release: release.parse().ok()?,
+            number: parse_number(number)?,
+        });
+    }
+    let Some((release, stage)) = id.split_once('-') else {
+        return id.parse().ok().map(VersionId::Release);
+    };
+    let release = release.parse().ok()?;
+    if let Some(number) = stage.strip_prefix("pre") {
+        Some(VersionId::PreRelease {
+            release,
+            number: parse_number(number)?,
+        })
+    } else if let Some(number) = stage.strip_prefix("rc") {
+        Some(VersionId::ReleaseCandidate {
+            release,
+            number: parse_number(number)?,
+        })
+    } else if let Some(number) = stage.strip_prefix("snapshot-") {
+        Some(VersionId::ReleaseSnapshot {
+            release,
+            number: parse_number(number)?,
+        })
+    } else {
+        None
+    }
+}
+/// Weekly snapshots such as `24w14a`
+fn parse_snapshot(id: &str) -> Option<VersionId> {
+    let (year, rest) = id.split_once('w')?;
+    // Checked before slicing by byte
+    if year.len() != 2 || rest.len() != 3 || !rest.is_ascii() {
+        return None;
+    }
+    let revision = rest.chars().last()?;
+    if !revision.is_ascii_lowercase() {
+        return None;
+    }
+    Some(VersionId::Snapshot {
+        year: parse_number(year)?,
+        week: parse_number(&rest[..2])?,
+        revision,
+    })
+}
+/// Alpha and Beta versions such as `a1.1.2_01` or `b1.3b`
+fn parse_old(id: &str) -> Option<VersionId> {
+    let (beta, version) = if let Some(version) = id.strip_prefix('b') {
+        (true, version)
+    } else {
+        (false, id.strip_prefix('a')?)
+    };
+    let split = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(version.len());
+    let (release, suffix) = version.split_at(split);
+    let valid_suffix = suffix.is_empty()
+        || suffix
+            .strip_prefix('_')
+            .is_some_and(|build| parse_number(build).is_some())
+        || (suffix.len() == 1 && suffix.chars().all(|c| c.is_ascii_lowercase()));
+    if !valid_suffix {
+        return None;
+    }
+    let release = release.parse().ok()?;
+    let suffix = suffix.to_owned();
+    Some(if beta {
+        VersionId::Beta { release, suffix }
+    } else {
+        VersionId::Alpha { release, suffix }
+    })
+}
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use crate::game_files::version_id::{ReleaseNumber, VersionId};
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            VersionId::parse("1.20.6"),
+            VersionId::Release(ReleaseNumber::new(1, 20, 6))
+        );
+        assert_eq!(
+            VersionId::parse("1.14 Pre-Release 1"),
+            VersionId::PreRelease {
+                release: ReleaseNumber::new(1, 14, 0),
+                number: 1
+            }
+        );
+        assert_eq!(
+            VersionId::parse("1.20.5-rc3"),
+            VersionId::ReleaseCandidate {
+                release: ReleaseNumber::new(1, 20, 5),
+                number: 3
+            }
+        );
+        assert_eq!(
+            VersionId::parse("24w14a"),
+            VersionId::Snapshot {
+                year: 24,
+                week: 14,
+                revision: 'a'
+            }
+        );
+        assert_eq!(
+            VersionId::parse("a1.1.2_01"),
+            VersionId::Alpha {
+                release: ReleaseNumber::new(1, 1, 2),
+                suffix: "_01".to_owned()
+            }
+        );
+        assert!(matches!(VersionId::parse("b1.3b"), VersionId::Beta { .. }));
+        for special in [
+            "24w14potato",
+            "3D Shareware v1.34",
+            "1.RV-Pre1",
+            "rd-132211",
+            "c0.30_01c",
+            "1.18_experimental-snapshot-1",
+        ] {
+            assert_eq!(
+                VersionId::parse(special),
+                VersionId::Special(special.to_owned())
+            );
+        }
+        for non_ascii in ["24wxé", "24wé", "é4w14a", "1.2é", "b1.é", "1.20-preé"] {
+            assert_eq!(
+                VersionId::parse(non_ascii),
+                VersionId::Special(non_ascii.to_owned())
+            );
+        }
+        for id in ["1.20.6", "1.21-pre1", "26.1-snapshot-2", "24w14a", "b1.7.3"] {
+            assert_eq!(VersionId::parse(id).to_string(), id);
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        let ordered = [
+            "a1.0.4",
+            "a1.1.2",
+            "a1.1.2_01",
+            "b1.7.3",
+            "1.0",
+            "1.12.2",
+            "1.13-pre1",
+            "1.13-pre8",
+            "1.13",
+            "1.20.5-rc1",
+            "1.20.5",
+            "26.1-snapshot-1",
+            "26.1",
+        ];
+        for window in ordered.windows(2) {
+            assert_eq!(
+                VersionId::parse(window[0]).partial_cmp(&VersionId::parse(window[1])),
+                Some(Ordering::Less),
+                "{} < {}",
+                window[0],
+                window[1]
+            );
+        }
+        assert!(VersionId::parse("24w14a") < VersionId::parse("24w14b"));
+        assert!(VersionId::parse("b1.8.1") < VersionId::parse("12w01a"));
+        // Requires the release time
+        assert_eq!(
+            VersionId::parse("24w14a").partial_cmp(&VersionId::parse("1.20.5")),
+            None
+        );
+        assert_eq!(
+            VersionId::parse("b1.3b").partial_cmp(&VersionId::parse("b1.3_01")),
+            None
+        );
+    }
+}
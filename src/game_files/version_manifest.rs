@@ -1,14 +1,22 @@
+use std::{cmp::Ordering, collections::HashMap};
+
 use chrono::{DateTime, Utc};
-use reqwest::Url;
-use serde::Deserialize;
+use reqwest::header::ACCEPT;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    game_files::{release::data::ReleaseData, version_type::VersionType},
-    mojang_time, APIClient, Error,
+    game_files::{
+        release::data::ReleaseData,
+        version_id::{ReleaseNumber, VersionId},
+        version_type::VersionType,
+    },
+    mojang_time,
+    utils::hash::verify_sha1,
+    APIClient, Error,
 };
 
 /// The main manliest file found at ['{launcher_meta}/mc/game/version_manifest_v2.json'](https://launchermeta.mojang.com/mc/game/version_manifest_v2.json)
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct VersionManifest {
     pub latest: Latest,
     pub versions: Vec<Version>,
@@ -17,22 +25,243 @@ impl VersionManifest {
     pub fn get_version(&self, id: &str) -> Option<&Version> {
         self.versions.iter().find(|version| version.id.eq(id))
     }
+    /// # Panics
+    /// If the latest snapshot is not in the manifest. See [VersionManifest::latest_snapshot]
     pub fn get_latest_snapshot(&self) -> &Version {
-        self.versions
-            .iter()
-            .find(|version| version.id.eq(&self.latest.snapshot))
+        self.latest_snapshot()
             .expect("The listed latest snapshot is not in the the version manifest?")
     }
-
+    /// # Panics
+    /// If the latest release is not in the manifest. See [VersionManifest::latest_release]
     pub fn get_latest_release(&self) -> &Version {
+        self.latest_release()
+            .expect("The listed latest release is not in the the version manifest?")
+    }
+    /// The latest snapshot. None if the manifest does not contain the version it lists as the latest
+    pub fn latest_snapshot(&self) -> Option<&Version> {
+        self.get_version(&self.latest.snapshot)
+    }
+    /// The latest release. None if the manifest does not contain the version it lists as the latest
+    pub fn latest_release(&self) -> Option<&Version> {
+        self.get_version(&self.latest.release)
+    }
+    /// All versions of the [VersionType]. Newest first
+    pub fn versions_of_type(&self, release_type: VersionType) -> impl Iterator<Item = &Version> {
         self.versions
             .iter()
-            .find(|version| version.id.eq(&self.latest.release))
-            .expect("The listed latest release is not in the the version manifest?")
+            .filter(move |version| version.release_type == release_type)
+    }
+    /// All versions ordered by release time. Oldest first
+    pub fn sorted_versions(&self) -> Vec<&Version> {
+        let mut versions: Vec<&Version> = self.versions.iter().rev().collect();
+        versions.sort_by_key(|version| version.release_time);
+        versions
+    }
+    /// All versions released between `from` and `to` inclusive. Oldest first
+    ///
+    /// Returns None if either version is not in the manifest
+    /// ```no_run
+    /// # use minecraft_rs::game_files::{version_manifest::VersionManifest, version_type::VersionType};
+    /// # fn releases(manifest: &VersionManifest) -> Option<()> {
+    /// let releases: Vec<_> = manifest
+    ///     .versions_between("1.16", "1.20.4")?
+    ///     .into_iter()
+    ///     .filter(|version| version.release_type == VersionType::Release)
+    ///     .collect();
+    /// # Some(())
+    /// # }
+    /// ```
+    pub fn versions_between(&self, from: &str, to: &str) -> Option<Vec<&Version>> {
+        let from = self.get_version(from)?.release_time;
+        let to = self.get_version(to)?.release_time;
+        Some(self.versions_released_between(from, to))
+    }
+    /// All versions with a release time between `from` and `to` inclusive. Oldest first
+    pub fn versions_released_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<&Version> {
+        self.sorted_versions()
+            .into_iter()
+            .filter(|version| version.release_time >= from && version.release_time <= to)
+            .collect()
+    }
+    /// The version released before `id`
+    pub fn previous_version(&self, id: &str) -> Option<&Version> {
+        self.previous_version_matching(id, |_| true)
+    }
+    /// The version of the [VersionType] released before `id`
+    pub fn previous_version_of_type(
+        &self,
+        id: &str,
+        release_type: VersionType,
+    ) -> Option<&Version> {
+        self.previous_version_matching(id, |version| version.release_type == release_type)
+    }
+    /// The version released after `id`
+    pub fn next_version(&self, id: &str) -> Option<&Version> {
+        self.next_version_matching(id, |_| true)
+    }
+    /// The version of the [VersionType] released after `id`
+    pub fn next_version_of_type(&self, id: &str, release_type: VersionType) -> Option<&Version> {
+        self.next_version_matching(id, |version| version.release_type == release_type)
+    }
+    fn previous_version_matching(
+        &self,
+        id: &str,
+        filter: impl Fn(&Version) -> bool,
+    ) -> Option<&Version> {
+        let sorted = self.sorted_versions();
+        let index = sorted.iter().position(|version| version.id == id)?;
+        sorted[..index]
+            .iter()
+            .rev()
+            .find(|version| filter(version))
+            .copied()
+    }
+    fn next_version_matching(
+        &self,
+        id: &str,
+        filter: impl Fn(&Version) -> bool,
+    ) -> Option<&Version> {
+        let sorted = self.sorted_versions();
+        let index = sorted.iter().position(|version| version.id == id)?;
+        sorted[index + 1..]
+            .iter()
+            .find(|version| filter(version))
+            .copied()
+    }
+    /// Groups the snapshots under the release they led to. Oldest first
+    ///
+    /// Pre-releases, release candidates and release snapshots belong to the release they target.
+    /// Weekly snapshots belong to the release ending their cycle. A patch release only ends the cycle if a pre-release or release snapshot
+    /// for it followed the weekly snapshots, or if no weekly snapshot follows it. Hotfixes such as 1.16.5 ship in the middle of the next cycle.
+    /// Once a release shipped in the middle of the cycle the weekly snapshots belong to the next x.y.0 release. The 14w snapshots were published
+    /// alongside 1.7.5 to 1.7.10 and lead to 1.8.
+    ///
+    /// The last cycle has no release if snapshots have been published since the latest release.
+    /// Special versions such as April Fools snapshots are not part of any cycle.
+    pub fn release_cycles(&self) -> Vec<ReleaseCycle<'_>> {
+        let sorted = self.sorted_versions();
+        let mut cycles = Vec::new();
+        let mut weekly = Vec::new();
+        let mut spans_release = false;
+        let mut targeted: HashMap<ReleaseNumber, Vec<&Version>> = HashMap::new();
+        for (index, version) in sorted.iter().copied().enumerate() {
+            let id = version.version_id();
+            match version.release_type {
+                VersionType::Release => {
+                    let mut snapshots = id
+                        .release_target()
+                        .and_then(|release| targeted.remove(&release))
+                        .unwrap_or_default();
+                    if ends_weekly_cycle(
+                        &id,
+                        &snapshots,
+                        &weekly,
+                        spans_release,
+                        &sorted[index + 1..],
+                    ) {
+                        snapshots.append(&mut weekly);
+                        spans_release = false;
+                    } else if !weekly.is_empty() {
+                        spans_release = true;
+                    }
+                    snapshots.sort_by_key(|snapshot| snapshot.release_time);
+                    cycles.push(ReleaseCycle {
+                        release: Some(version),
+                        snapshots,
+                    });
+                }
+                VersionType::Snapshot if !id.is_special() => match id.release_target() {
+                    Some(release) => targeted.entry(release).or_default().push(version),
+                    None => weekly.push(version),
+                },
+                _ => {}
+            }
+        }
+        let mut snapshots: Vec<&Version> = weekly
+            .into_iter()
+            .chain(targeted.into_values().flatten())
+            .collect();
+        if !snapshots.is_empty() {
+            snapshots.sort_by_key(|snapshot| snapshot.release_time);
+            cycles.push(ReleaseCycle {
+                release: None,
+                snapshots,
+            });
+        }
+        cycles
+    }
+    /// The snapshots that led to the release `id`. Oldest first
+    pub fn snapshots_for_release(&self, id: &str) -> Option<Vec<&Version>> {
+        self.release_cycles()
+            .into_iter()
+            .find(|cycle| cycle.release.is_some_and(|release| release.id == id))
+            .map(|cycle| cycle.snapshots)
+    }
+    /// Compares two version ids.
+    ///
+    /// Uses the [VersionId] ordering when the ids are enough. Otherwise the release time of the versions is compared.
+    /// Returns None if the order can not be decided and one of the versions is not in the manifest
+    pub fn compare_versions(&self, a: &str, b: &str) -> Option<Ordering> {
+        if let Some(ordering) = VersionId::parse(a).partial_cmp(&VersionId::parse(b)) {
+            return Some(ordering);
+        }
+        let a = self.get_version(a)?;
+        let b = self.get_version(b)?;
+        Some(a.release_time.cmp(&b.release_time))
     }
 }
+/// If the release ends the cycle of the open weekly snapshots. `later` are the versions released after it.
+///
+/// `spans_release` is true if another release shipped since the first of the open weekly snapshots
+fn ends_weekly_cycle(
+    release: &VersionId,
+    targeted: &[&Version],
+    weekly: &[&Version],
+    spans_release: bool,
+    later: &[&Version],
+) -> bool {
+    let Some(last_weekly) = weekly.last() else {
+        return true;
+    };
+    if release
+        .release_target()
+        .is_none_or(|release| release.patch == 0)
+    {
+        return true;
+    }
+    if spans_release {
+        return false;
+    }
+    let announced = targeted.iter().any(|version| {
+        version.release_time > last_weekly.release_time
+            && matches!(
+                version.version_id(),
+                VersionId::PreRelease { .. } | VersionId::ReleaseSnapshot { .. }
+            )
+    });
+    announced
+        || !later
+            .iter()
+            .take_while(|version| version.release_type != VersionType::Release)
+            .any(|version| {
+                let id = version.version_id();
+                id.is_snapshot() && !id.is_special() && id.release_target().is_none()
+            })
+}
+/// A release and the snapshots published before it. Created by [VersionManifest::release_cycles]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseCycle<'a> {
+    /// None for the snapshots of an unreleased version
+    pub release: Option<&'a Version>,
+    /// Oldest first
+    pub snapshots: Vec<&'a Version>,
+}
 /// The latest version information provided by the api
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Latest {
     /// Latest Release
     pub release: String,
@@ -42,7 +271,7 @@ pub struct Latest {
 
 /// The Version found within the manifest file.
 /// Give basic information about the version
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Version {
     /// The Release Type
     #[serde(rename = "type")]
@@ -61,27 +290,259 @@ pub struct Version {
 }
 
 impl Version {
+    /// Parses the [id](Version::id)
+    pub fn version_id(&self) -> VersionId {
+        VersionId::parse(&self.id)
+    }
     /// Uses the url found inside the Version to pull the Release Data.
-    /// ```
-    /// #[tokio::main]
-    /// async fn main() {
-    ///     let client = crate::test::setup();
-    ///     let version_manifest = client.version_manifest().await?;
-    ///     println!("Latest Release Info {:#?}", version_manifest.latest);
-    ///     let snapshot = version_manifest
-    ///         .get_latest_snapshot()
-    ///         .get_release(&client)
-    ///         .await?;
-    ///     println!("Snapshot Release Info {:#?}", snapshot);
     ///
-    ///     Ok(())
-    /// }
+    /// The response is verified against [Version::sha1] before it is parsed
+    /// ```no_run
+    /// # async fn release(client: minecraft_rs::APIClient) -> Result<(), minecraft_rs::Error> {
+    /// let version_manifest = client.version_manifest().await?;
+    /// println!("Latest Release Info {:#?}", version_manifest.latest);
+    /// let snapshot = version_manifest
+    ///     .get_latest_snapshot()
+    ///     .get_release(&client)
+    ///     .await?;
+    /// println!("Snapshot Release Info {:#?}", snapshot);
+    /// # Ok(())
+    /// # }
     /// ```
     pub async fn get_release(&self, client: &APIClient) -> Result<ReleaseData, Error> {
-        let url = Url::parse(&self.url).unwrap();
-        let release = client
-            .process_json::<ReleaseData>(client.http_client.get(url))
-            .await?;
+        let bytes = self.get_release_bytes(client).await?;
+        let mut release: ReleaseData = serde_json::from_slice(&bytes)?;
+        release.rewrite_urls(&client.game_files);
         Ok(release)
     }
+    /// Pulls the Release Data without parsing it. Returning exactly what was served
+    ///
+    /// The bytes are verified against [Version::sha1]
+    pub async fn get_release_bytes(&self, client: &APIClient) -> Result<Vec<u8>, Error> {
+        let url = client.game_files.redirect(&self.url)?;
+        let bytes = client
+            .process_bytes(
+                client
+                    .http_client
+                    .get(url)
+                    .header(ACCEPT, "application/json"),
+            )
+            .await?;
+        verify_sha1(&self.url, &bytes, &self.sha1)?;
+        Ok(bytes)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use serde_json::json;
+
+    use crate::{
+        game_files::{version_manifest::VersionManifest, version_type::VersionType},
+        utils::hash::sha1_hex,
+        Error,
+    };
+
+    fn ids<'a>(versions: impl IntoIterator<Item = &'a super::Version>) -> Vec<&'a str> {
+        versions
+            .into_iter()
+            .map(|version| version.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn latest() {
+        let mut manifest = crate::test::version_manifest();
+        assert_eq!(manifest.get_latest_release().id, "1.20.5");
+        manifest.latest.snapshot = "24w99a".to_owned();
+        assert_eq!(manifest.latest_snapshot(), None);
+    }
+
+    #[test]
+    fn filters_and_ranges() {
+        let manifest = crate::test::version_manifest();
+        assert_eq!(
+            ids(manifest.versions_of_type(VersionType::Release)),
+            ["1.20.5", "1.20.4", "1.20.3"]
+        );
+        let releases = manifest
+            .versions_between("1.20.3", "1.20.5")
+            .unwrap()
+            .into_iter()
+            .filter(|version| version.release_type == VersionType::Release);
+        assert_eq!(ids(releases), ["1.20.3", "1.20.4", "1.20.5"]);
+        assert_eq!(manifest.versions_between("1.20.3", "1.21"), None);
+    }
+
+    #[test]
+    fn previous_and_next() {
+        let manifest = crate::test::version_manifest();
+        assert_eq!(
+            manifest.previous_version("1.20.5").map(|v| v.id.as_str()),
+            Some("1.20.5-rc1")
+        );
+        assert_eq!(
+            manifest
+                .previous_version_of_type("1.20.5", VersionType::Release)
+                .map(|v| v.id.as_str()),
+            Some("1.20.4")
+        );
+        assert_eq!(
+            manifest.next_version("1.20.4").map(|v| v.id.as_str()),
+            Some("23w51b")
+        );
+        assert_eq!(manifest.next_version("1.20.5"), None);
+        assert_eq!(manifest.previous_version("a1.0.4"), None);
+    }
+
+    #[test]
+    fn release_cycles() {
+        let manifest = crate::test::version_manifest();
+        assert_eq!(
+            ids(manifest.snapshots_for_release("1.20.5").unwrap()),
+            ["23w51b", "24w13a", "24w14a", "1.20.5-rc1"]
+        );
+        assert_eq!(
+            ids(manifest.snapshots_for_release("1.20.4").unwrap()),
+            ["1.20.4-rc1"]
+        );
+        assert!(manifest
+            .release_cycles()
+            .iter()
+            .all(|cycle| cycle.release.is_some()));
+    }
+
+    #[test]
+    fn interleaved_patch_release() -> anyhow::Result<()> {
+        let version = |id: &str, release_type: &str, release_time: &str| {
+            json!({
+                "id": id,
+                "type": release_type,
+                "url": format!("https://piston-meta.mojang.com/v1/packages/{id}.json"),
+                "time": release_time,
+                "releaseTime": release_time,
+                "sha1": "0000000000000000000000000000000000000000",
+                "complianceLevel": 1
+            })
+        };
+        let manifest: VersionManifest = serde_json::from_value(json!({
+            "latest": { "release": "1.17", "snapshot": "21w37a" },
+            "versions": [
+                version("21w37a", "snapshot", "2021-09-15T14:00:00+00:00"),
+                version("1.17.1", "release", "2021-07-06T12:00:00+00:00"),
+                version("1.17.1-pre1", "snapshot", "2021-06-18T12:00:00+00:00"),
+                version("1.17", "release", "2021-06-08T11:00:00+00:00"),
+                version("1.17-pre1", "snapshot", "2021-05-27T09:39:21+00:00"),
+                version("21w03a", "snapshot", "2021-01-20T13:48:42+00:00"),
+                version("1.16.5", "release", "2021-01-15T14:14:48+00:00"),
+                version("1.16.5-rc1", "snapshot", "2021-01-13T15:07:32+00:00"),
+                version("20w51a", "snapshot", "2020-12-16T14:56:13+00:00"),
+                version("20w45a", "snapshot", "2020-11-04T15:05:19+00:00"),
+                version("1.16.4", "release", "2020-10-29T15:49:37+00:00"),
+                version("1.16.2", "release", "2020-08-11T10:13:46+00:00"),
+                version("1.16.2-pre1", "snapshot", "2020-07-29T13:42:55+00:00"),
+                version("20w28a", "snapshot", "2020-07-08T14:40:11+00:00"),
+                version("20w27a", "snapshot", "2020-07-01T14:33:34+00:00"),
+                version("1.16.1", "release", "2020-06-24T10:31:40+00:00"),
+                version("1.8", "release", "2014-09-02T08:24:35+00:00"),
+                version("1.8-pre1", "snapshot", "2014-08-28T09:29:08+00:00"),
+                version("14w27a", "snapshot", "2014-07-02T15:33:47+00:00"),
+                version("1.7.10", "release", "2014-06-26T09:50:13+00:00"),
+                version("14w26a", "snapshot", "2014-06-25T15:29:54+00:00"),
+                version("1.7.10-pre1", "snapshot", "2014-06-19T14:40:18+00:00"),
+                version("14w25a", "snapshot", "2014-06-18T14:38:23+00:00"),
+                version("1.7.9", "release", "2014-04-14T13:29:23+00:00"),
+                version("1.7.6", "release", "2014-04-09T07:52:16+00:00"),
+                version("1.7.6-pre1", "snapshot", "2014-04-03T12:49:47+00:00"),
+                version("14w11a", "snapshot", "2014-03-13T14:42:10+00:00"),
+                version("1.7.5", "release", "2014-02-26T09:39:17+00:00"),
+                version("14w02a", "snapshot", "2014-01-09T15:05:14+00:00"),
+                version("1.7.4", "release", "2013-12-10T12:00:00+00:00")
+            ]
+        }))?;
+        assert_eq!(
+            ids(manifest.snapshots_for_release("1.7.6").unwrap()),
+            ["1.7.6-pre1"]
+        );
+        assert_eq!(
+            ids(manifest.snapshots_for_release("1.7.10").unwrap()),
+            ["1.7.10-pre1"]
+        );
+        assert_eq!(
+            ids(manifest.snapshots_for_release("1.8").unwrap()),
+            ["14w02a", "14w11a", "14w25a", "14w26a", "14w27a", "1.8-pre1"]
+        );
+        assert_eq!(
+            ids(manifest.snapshots_for_release("1.16.2").unwrap()),
+            ["20w27a", "20w28a", "1.16.2-pre1"]
+        );
+        assert_eq!(
+            ids(manifest.snapshots_for_release("1.16.5").unwrap()),
+            ["1.16.5-rc1"]
+        );
+        assert_eq!(
+            ids(manifest.snapshots_for_release("1.17").unwrap()),
+            ["20w45a", "20w51a", "21w03a", "1.17-pre1"]
+        );
+        assert_eq!(
+            ids(manifest.snapshots_for_release("1.17.1").unwrap()),
+            ["1.17.1-pre1"]
+        );
+        let cycles = manifest.release_cycles();
+        let open = cycles.last().unwrap();
+        assert_eq!(open.release, None);
+        assert_eq!(ids(open.snapshots.iter().copied()), ["21w37a"]);
+        Ok(())
+    }
+
+    #[test]
+    fn compare_versions() {
+        let manifest = crate::test::version_manifest();
+        assert_eq!(
+            manifest.compare_versions("1.20.4", "1.20.5-rc1"),
+            Some(Ordering::Less)
+        );
+        // Decided by the release time
+        assert_eq!(
+            manifest.compare_versions("24w14a", "1.20.4"),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            manifest.compare_versions("24w14potato", "24w14a"),
+            Some(Ordering::Less)
+        );
+        assert_eq!(manifest.compare_versions("24w14a", "1.21"), None);
+    }
+
+    #[tokio::test]
+    async fn verified_release() -> anyhow::Result<()> {
+        let client = crate::test::setup();
+        let server = crate::test::TestServer::start().await?;
+        let body = serde_json::to_vec(&crate::test::release_json("1.20.4"))?;
+        server.insert("/v1/packages/1.20.4.json", body.clone());
+
+        let mut manifest = crate::test::version_manifest();
+        let version = manifest
+            .versions
+            .iter_mut()
+            .find(|version| version.id == "1.20.4")
+            .unwrap();
+        version.url = server.url("/v1/packages/1.20.4.json");
+        version.sha1 = sha1_hex(&body);
+        assert_eq!(version.get_release_bytes(&client).await?, body);
+        assert_eq!(version.get_release(&client).await?.id, "1.20.4");
+
+        version.sha1 = sha1_hex(b"Not the release");
+        assert!(matches!(
+            version.get_release(&client).await,
+            Err(Error::HashMismatch(_))
+        ));
+        version.url = "Not a URL".to_owned();
+        assert!(matches!(
+            version.get_release(&client).await,
+            Err(Error::URLParse(_))
+        ));
+        Ok(())
+    }
 }
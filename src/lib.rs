@@ -6,7 +6,10 @@ use serde::de::DeserializeOwned;
 use tracing::{debug, trace};
 
 pub use crate::error::Error;
-use crate::http::IntoResult;
+use crate::{
+    http::IntoResult,
+    utils::{content_store::ContentStore, rate_limit::RateLimiter},
+};
 
 pub mod error;
 pub mod game_files;
@@ -20,18 +23,32 @@ pub mod authentication {
 
 #[derive(Clone, Debug)]
 pub struct APIClient(pub(crate) Arc<InnerAPIClient>);
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct InnerAPIClient {
     pub(crate) http_client: Client,
     pub(crate) game_files: GameFilesAPIBuilder,
+    pub(crate) content_store: Option<ContentStore>,
+    pub(crate) rate_limiter: Option<RateLimiter>,
 }
 impl APIClient {
     pub fn new(client: Client, game_files: GameFilesAPIBuilder) -> Self {
         Self(Arc::new(InnerAPIClient {
             http_client: client,
             game_files,
+            content_store: None,
+            rate_limiter: None,
         }))
     }
+    /// Downloads with a known SHA-1 go through the store. Placing the files with hard links where possible
+    pub fn with_content_store(mut self, content_store: ContentStore) -> Self {
+        Arc::make_mut(&mut self.0).content_store = Some(content_store);
+        self
+    }
+    /// Throttles every file download of the client. Such as a background update
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        Arc::make_mut(&mut self.0).rate_limiter = Some(rate_limiter);
+        self
+    }
 }
 impl Deref for APIClient {
     type Target = InnerAPIClient;
@@ -55,19 +72,41 @@ impl APIClient {
         trace!(?text);
         serde_json::from_str(&text).map_err(Error::from)
     }
+    #[tracing::instrument]
+    pub(crate) async fn process_bytes(&self, request: RequestBuilder) -> Result<Vec<u8>, Error> {
+        let request = request.build()?;
+        let response = self.0.http_client.execute(request).await?;
+        debug!(?response);
+        let bytes = response.into_result::<Error>().await?.bytes().await?;
+        trace!(len = bytes.len());
+        Ok(bytes.to_vec())
+    }
 }
 #[cfg(test)]
 pub(crate) mod test {
 
-    use std::sync::Once;
+    use std::{
+        borrow::Cow,
+        collections::HashMap,
+        path::Path,
+        sync::{Arc, Mutex, Once},
+    };
 
-    use reqwest::ClientBuilder;
+    use reqwest::{Client, ClientBuilder};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
     use tracing::level_filters::LevelFilter;
     use tracing_subscriber::{
         filter, fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
     };
 
-    use crate::{game_files::GameFilesAPIBuilder, APIClient};
+    use crate::{
+        game_files::{version_manifest::VersionManifest, GameFilesAPIBuilder},
+        utils::hash::sha1_hex,
+        APIClient,
+    };
 
     static ENV_FILE: &str = "minecraft-rs.test.env";
     pub fn setup() -> APIClient {
@@ -108,4 +147,171 @@ pub(crate) mod test {
             GameFilesAPIBuilder::default(),
         )
     }
+    /// A small version manifest that does not require any requests to Mojang. Newest versions first like the real manifest
+    pub fn version_manifest_json() -> serde_json::Value {
+        let version = |id: &str, release_type: &str, release_time: &str| {
+            serde_json::json!({
+                "id": id,
+                "type": release_type,
+                "url": format!("https://piston-meta.mojang.com/v1/packages/0000000000000000000000000000000000000000/{id}.json"),
+                "time": release_time,
+                "releaseTime": release_time,
+                "sha1": "0000000000000000000000000000000000000000",
+                "complianceLevel": 1
+            })
+        };
+        serde_json::json!({
+            "latest": { "release": "1.20.5", "snapshot": "1.20.5" },
+            "versions": [
+                version("1.20.5", "release", "2024-04-23T12:33:11+00:00"),
+                version("1.20.5-rc1", "snapshot", "2024-04-18T12:00:00+00:00"),
+                version("24w14a", "snapshot", "2024-04-03T12:48:39+00:00"),
+                version("24w14potato", "snapshot", "2024-04-01T11:30:45+00:00"),
+                version("24w13a", "snapshot", "2024-03-27T13:48:31+00:00"),
+                version("23w51b", "snapshot", "2023-12-18T15:14:34+00:00"),
+                version("1.20.4", "release", "2023-12-07T12:56:20+00:00"),
+                version("1.20.4-rc1", "snapshot", "2023-12-06T13:24:57+00:00"),
+                version("1.20.3", "release", "2023-12-05T12:10:32+00:00"),
+                version("b1.7.3", "old_beta", "2011-07-07T22:00:00+00:00"),
+                version("a1.0.4", "old_alpha", "2010-07-08T22:00:00+00:00"),
+            ]
+        })
+    }
+    pub fn version_manifest() -> VersionManifest {
+        serde_json::from_value(version_manifest_json()).expect("Invalid test manifest")
+    }
+    /// A minimal release json that does not require any requests to Mojang
+    pub fn release_json(id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "downloads": {
+                "client": {
+                    "sha1": "0000000000000000000000000000000000000000",
+                    "size": 0,
+                    "url": format!("https://piston-data.mojang.com/v1/objects/0000000000000000000000000000000000000000/{id}.jar")
+                }
+            },
+            "libraries": [],
+            "assetIndex": {
+                "id": id,
+                "sha1": "0000000000000000000000000000000000000000",
+                "size": 0,
+                "url": format!("https://piston-meta.mojang.com/v1/packages/0000000000000000000000000000000000000000/{id}.json")
+            },
+            "assets": id,
+            "minimumLauncherVersion": 21,
+            "type": "release",
+            "id": id,
+            "releaseTime": "2019-01-01T00:00:00+00:00",
+            "time": "2019-01-01T00:00:00+00:00",
+            "complianceLevel": 1,
+            "mainClass": "net.minecraft.client.main.Main",
+            "javaVersion": { "component": "jre-legacy", "majorVersion": 8 }
+        })
+    }
+    /// Writes the file. Creating the parent directories
+    pub fn write(path: &Path, body: &[u8]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, body).unwrap();
+    }
+    /// A client reading every endpoint from below the base. From `/resources`, `/libraries` and `/launcher_meta`
+    pub fn client(base: &str) -> APIClient {
+        APIClient::new(
+            Client::new(),
+            GameFilesAPIBuilder {
+                resource_base: Cow::Owned(format!("{base}/resources")),
+                library_base: Cow::Owned(format!("{base}/libraries")),
+                launcher_meta: Cow::Owned(format!("{base}/launcher_meta")),
+                ..Default::default()
+            },
+        )
+    }
+    /// A HTTP server on localhost serving files from memory. Responses carry an ETag and honor `If-None-Match`
+    #[derive(Debug, Clone)]
+    pub struct TestServer {
+        base: String,
+        files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+        failure: Arc<Mutex<Option<u16>>>,
+    }
+    impl TestServer {
+        pub async fn start() -> std::io::Result<Self> {
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let server = TestServer {
+                base: format!("http://{}", listener.local_addr()?),
+                files: Default::default(),
+                failure: Default::default(),
+            };
+            let handler = server.clone();
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let handler = handler.clone();
+                    tokio::spawn(async move {
+                        let mut request = Vec::new();
+                        let mut buffer = [0u8; 1024];
+                        while !request.ends_with(b"\r\n\r\n") {
+                            match stream.read(&mut buffer).await {
+                                Ok(0) | Err(_) => return,
+                                Ok(read) => request.extend_from_slice(&buffer[..read]),
+                            }
+                        }
+                        let response = handler.respond(&String::from_utf8_lossy(&request));
+                        let _ = stream.write_all(&response).await;
+                    });
+                }
+            });
+            Ok(server)
+        }
+        fn respond(&self, request: &str) -> Vec<u8> {
+            if let Some(status) = *self.failure.lock().unwrap() {
+                return format!(
+                    "HTTP/1.1 {status} Failure\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .into_bytes();
+            }
+            let path = request.split(' ').nth(1).unwrap_or("/");
+            let if_none_match = request.lines().find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case("if-none-match")
+                    .then(|| value.trim().to_owned())
+            });
+            let Some(body) = self.files.lock().unwrap().get(path).cloned() else {
+                return b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_vec();
+            };
+            let etag = format!("\"{}\"", sha1_hex(&body));
+            if if_none_match.as_deref() == Some(etag.as_str()) {
+                return format!(
+                    "HTTP/1.1 304 Not Modified\r\nETag: {etag}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .into_bytes();
+            }
+            let mut response = format!(
+                "HTTP/1.1 200 OK\r\nETag: {etag}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .into_bytes();
+            response.extend_from_slice(&body);
+            response
+        }
+        /// The base URL of the server. Without a trailing slash
+        pub fn base(&self) -> &str {
+            &self.base
+        }
+        pub fn url(&self, path: &str) -> String {
+            format!("{}{path}", self.base)
+        }
+        /// A [client] for the server. Endpoints are served from `/resources`, `/libraries` and `/launcher_meta`
+        pub fn client(&self) -> APIClient {
+            client(self.base())
+        }
+        /// Responds to every request with the status
+        pub fn fail_with(&self, status: u16) {
+            *self.failure.lock().unwrap() = Some(status);
+        }
+        pub fn insert(&self, path: &str, body: impl Into<Vec<u8>>) {
+            self.files
+                .lock()
+                .unwrap()
+                .insert(path.to_owned(), body.into());
+        }
+    }
 }
@@ -0,0 +1,254 @@
+//! A download cache addressed by SHA-1. Shared by every instance and installation using it.
+//!
+//! Files are stored at `{root}/{xx}/{sha1}` and hard linked into place. Copied if the destination is on another filesystem
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use reqwest::{Client, Url};
+use tokio::fs::{copy, create_dir_all, hard_link, metadata, remove_file, rename};
+use tracing::{debug, warn};
+
+use crate::{
+    error::HashMismatch,
+    utils::{
+        download_from_mirrors_limited, hash::sha1_file, rate_limit::RateLimiter, temporary_path,
+    },
+    Error,
+};
+
+/// How a file was placed by [ContentStore::place]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Linked,
+    Copied,
+}
+
+/// Set on the [APIClient](crate::APIClient) with [with_content_store](crate::APIClient::with_content_store)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentStore {
+    root: PathBuf,
+}
+impl ContentStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+    /// `{root}/{xx}/{sha1}`
+    pub fn path(&self, sha1: &str) -> PathBuf {
+        let sha1 = sha1.to_ascii_lowercase();
+        self.root
+            .join(sha1.get(..2).unwrap_or_default())
+            .join(&sha1)
+    }
+    /// Files are verified when added. So they are not hashed again. See [ContentStore::download] for when they are
+    pub async fn contains(&self, sha1: &str) -> bool {
+        metadata(self.path(sha1)).await.is_ok()
+    }
+    /// Adds an existing file to the store. Fails if the file does not match the SHA-1
+    pub async fn insert(&self, source: &Path, sha1: &str) -> Result<PathBuf, Error> {
+        let stored = self.path(sha1);
+        if self.contains(sha1).await {
+            return Ok(stored);
+        }
+        let actual = sha1_file(source).await?;
+        if !actual.eq_ignore_ascii_case(sha1) {
+            return Err(HashMismatch {
+                location: source.to_string_lossy().into_owned(),
+                expected: sha1.to_owned(),
+                actual,
+            }
+            .into());
+        }
+        let temporary = self.temporary_path(sha1);
+        link_or_copy(source, &temporary).await?;
+        rename(&temporary, &stored).await?;
+        Ok(stored)
+    }
+    /// Places the stored file at `destination`. Replacing any existing file
+    pub async fn place(&self, sha1: &str, destination: &Path) -> Result<Placement, Error> {
+        match remove_file(destination).await {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+        link_or_copy(&self.path(sha1), destination).await
+    }
+    /// Places the file at `destination`. Downloading it into the store first if it is not stored.
+    ///
+    /// The stored file is hashed again if `destination` exists. Replacing a file is usually a repair. The destination may be a link to the same corrupt stored file
+    ///
+    /// Returns the URL that served the file. A `file` URL of the stored file if it was not downloaded
+    pub async fn download<F>(
+        &self,
+        urls: &[Url],
+        reqwest: &Client,
+        sha1: &str,
+        destination: &Path,
+        limiters: &[&RateLimiter],
+        subscriber: F,
+    ) -> Result<Url, Error>
+    where
+        F: Fn(usize),
+    {
+        let stored = self.path(sha1);
+        let url = if self.is_intact(sha1, destination).await? {
+            Url::from_file_path(&stored)
+                .map_err(|_| Error::Custom(format!("{stored:?} is not an absolute path")))?
+        } else {
+            let temporary = self.temporary_path(sha1);
+            let url = download_from_mirrors_limited(
+                urls,
+                reqwest,
+                temporary.clone(),
+                limiters,
+                subscriber,
+            )
+            .await?;
+            let actual = sha1_file(&temporary).await?;
+            if !actual.eq_ignore_ascii_case(sha1) {
+                remove_file(&temporary).await?;
+                return Err(HashMismatch {
+                    location: url.into(),
+                    expected: sha1.to_owned(),
+                    actual,
+                }
+                .into());
+            }
+            rename(&temporary, &stored).await?;
+            debug!(%url, ?stored, "Stored");
+            url
+        };
+        self.place(sha1, destination).await?;
+        Ok(url)
+    }
+    /// If the file is stored. Removes the stored file if `destination` exists and the stored file does not match the SHA-1
+    async fn is_intact(&self, sha1: &str, destination: &Path) -> Result<bool, Error> {
+        if !self.contains(sha1).await {
+            return Ok(false);
+        }
+        if metadata(destination).await.is_err() {
+            return Ok(true);
+        }
+        let stored = self.path(sha1);
+        if sha1_file(&stored).await?.eq_ignore_ascii_case(sha1) {
+            return Ok(true);
+        }
+        warn!(?stored, "The stored file is corrupt. Downloading it again");
+        remove_file(&stored).await?;
+        Ok(false)
+    }
+    fn temporary_path(&self, sha1: &str) -> PathBuf {
+        temporary_path(&self.path(sha1))
+    }
+}
+
+/// Hard links the file. Copies it if linking fails
+async fn link_or_copy(source: &Path, destination: &Path) -> Result<Placement, Error> {
+    if let Some(parent) = destination.parent() {
+        create_dir_all(parent).await?;
+    }
+    if hard_link(source, destination).await.is_ok() {
+        return Ok(Placement::Linked);
+    }
+    copy(source, destination).await?;
+    Ok(Placement::Copied)
+}
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        game_files::{
+            assets::{data::AssetFile, store::object_path},
+            installation::Installation,
+            release::{data::ReleaseData, platform::Platform},
+        },
+        test::write,
+        utils::{content_store::ContentStore, hash::sha1_hex},
+    };
+
+    #[tokio::test]
+    async fn shared_downloads() -> anyhow::Result<()> {
+        let server = crate::test::TestServer::start().await?;
+        let hash = sha1_hex(b"icon");
+        server.insert(
+            &format!("/resources/{}/{hash}", &hash[..2]),
+            b"icon".to_vec(),
+        );
+        let dir = tempfile::tempdir()?;
+        let store = ContentStore::new(dir.path().join("store"));
+        let client = server.client().with_content_store(store.clone());
+        let index: AssetFile = serde_json::from_value(
+            serde_json::json!({ "objects": { "icon.png": { "hash": hash, "size": 4 } } }),
+        )?;
+
+        for instance in ["a", "b"] {
+            let assets = dir.path().join(instance);
+            let plan = index
+                .clone()
+                .download(client.clone(), assets.clone())
+                .await?;
+            for download in plan.to_download.into_values() {
+                download.download().await?;
+            }
+            assert_eq!(std::fs::read(object_path(&assets, &hash))?, b"icon");
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let stored = std::fs::metadata(store.path(&hash))?;
+            assert_eq!(stored.nlink(), 3);
+        }
+
+        std::fs::write(dir.path().join("corrupt"), b"icom")?;
+        assert!(store
+            .insert(&dir.path().join("corrupt"), &sha1_hex(b"icon?"))
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn repair_linked_file() -> anyhow::Result<()> {
+        let server = crate::test::TestServer::start().await?;
+        server.insert("/client.jar", b"client".to_vec());
+        let dir = tempfile::tempdir()?;
+        let store = ContentStore::new(dir.path().join("store"));
+        let client = server.client().with_content_store(store.clone());
+        let installation = Installation::new(dir.path().join("game"));
+        let index = serde_json::to_vec(&json!({ "objects": {} }))?;
+        write(&installation.asset_index_path("1.12"), &index);
+        let mut release = crate::test::release_json("1.12.2");
+        release["downloads"]["client"] = json!({
+            "sha1": sha1_hex(b"client"),
+            "size": 6,
+            "url": server.url("/client.jar")
+        });
+        release["assetIndex"] = json!({
+            "id": "1.12",
+            "sha1": sha1_hex(&index),
+            "size": index.len(),
+            "url": server.url("/index.json")
+        });
+        let release: ReleaseData = serde_json::from_value(release)?;
+        let linux = Platform::new("linux", "x86_64");
+
+        let report = installation.verify(&client, &release, &linux).await?;
+        assert_eq!(installation.repair(&client, &report).await?.len(), 1);
+        // Written through the link. The stored file is corrupt as well
+        std::fs::write(installation.client_jar_path("1.12.2"), b"cliend")?;
+
+        let report = installation.verify(&client, &release, &linux).await?;
+        assert_eq!(report.corrupt.len(), 1);
+        assert_eq!(installation.repair(&client, &report).await?.len(), 1);
+        assert!(installation
+            .verify(&client, &release, &linux)
+            .await?
+            .is_ok());
+        assert_eq!(std::fs::read(store.path(&sha1_hex(b"client")))?, b"client");
+        Ok(())
+    }
+}
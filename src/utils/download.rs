@@ -5,61 +5,109 @@ use std::{
 
 use reqwest::{Response, Url};
 
-use crate::{APIClient, Error};
+use crate::{utils::rate_limit::RateLimiter, APIClient, Error};
 
 /// A generic Download handler and type
 /// Contains a Response that we wrap to to make file downloading easy
 #[derive(Clone)]
 pub struct Download {
-    /// URL to the download
-    pub(crate) url: Url,
+    /// URLs to the download. In the order they are tried
+    pub(crate) urls: Vec<Url>,
     /// The number of bytes the download is
     pub file_size: usize,
+    /// Allows the download to use the [ContentStore](super::content_store::ContentStore) of the client
+    pub(crate) sha1: Option<String>,
+    /// Applied on top of the rate limiter of the client
+    pub(crate) rate_limiter: Option<RateLimiter>,
     /// A Reference to the API Client
     pub(crate) client: APIClient,
 }
 
 impl Download {
+    /// The URLs the download is pulled from. In the order they are tried
+    pub fn urls(&self) -> &[Url] {
+        &self.urls
+    }
+    /// Throttles this download. The limiter can be shared with other downloads
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+    /// The rate limiters of the client and the download
+    fn limiters(&self) -> Vec<&RateLimiter> {
+        self.client
+            .rate_limiter
+            .iter()
+            .chain(self.rate_limiter.iter())
+            .collect()
+    }
     /// Downloads a file. If the file already exists. it will be overwritten
-    pub async fn download(self, location: PathBuf) -> Result<(), Error> {
+    ///
+    /// Returns the URL that served the file
+    pub async fn download(self, location: PathBuf) -> Result<Url, Error> {
         self.download_with_subscriber(location, |_| {}).await
     }
     /// Downloads a file. if a file already exists. it will be overwritten
     /// `subscriber` is a function that is called whenever a new set of bytes is downloaded and written. Param is the number of bytes download
+    ///
+    /// Returns the URL that served the file
     pub async fn download_with_subscriber<F>(
         self,
         location: PathBuf,
         subscriber: F,
-    ) -> Result<(), Error>
+    ) -> Result<Url, Error>
     where
         F: Fn(usize),
     {
-        super::download_with_subscriber(self.url, &self.client.http_client, location, subscriber)
-            .await
+        let limiters = self.limiters();
+        if let (Some(store), Some(sha1)) = (&self.client.content_store, &self.sha1) {
+            return store
+                .download(
+                    &self.urls,
+                    &self.client.http_client,
+                    sha1,
+                    &location,
+                    &limiters,
+                    subscriber,
+                )
+                .await;
+        }
+        super::download_from_mirrors_limited(
+            &self.urls,
+            &self.client.http_client,
+            location,
+            &limiters,
+            subscriber,
+        )
+        .await
     }
-    /// Returns the bytes for the download
+    /// Returns the bytes for the download. Throttled by the rate limiters
     pub async fn get_bytes(self) -> Result<Vec<u8>, Error> {
-        Ok(self.to_request().await?.bytes().await?.to_vec())
+        let limiters: Vec<RateLimiter> = self.limiters().into_iter().cloned().collect();
+        let mut response = self.to_request().await?;
+        let mut bytes = Vec::with_capacity(response.content_length().unwrap_or_default() as usize);
+        while let Some(chunk) = response.chunk().await? {
+            for limiter in &limiters {
+                limiter.acquire(chunk.len()).await;
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
     }
     /// Turns the Download into a request. Allowing full control of the download
+    ///
+    /// Mirrors are tried in order on connection errors and 5xx responses
     pub async fn to_request(self) -> Result<Response, Error> {
-        self.client
-            .http_client
-            .get(self.url)
-            .send()
-            .await
-            .map_err(Error::from)
+        let (response, _) =
+            super::send_with_failover(&self.urls, |url| self.client.http_client.get(url)).await?;
+        Ok(response)
     }
 }
 
 impl Debug for Download {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "URL {} with size of {}",
-            self.url.as_str(),
-            self.file_size
-        )
+        let urls: Vec<&str> = self.urls.iter().map(Url::as_str).collect();
+        write!(f, "URLs {:?} with size of {}", urls, self.file_size)
     }
 }
 
@@ -73,23 +121,28 @@ impl DownloadToFile {
     pub(crate) fn new(download: Download, location: PathBuf) -> DownloadToFile {
         DownloadToFile { location, download }
     }
+    /// Throttles this download. The limiter can be shared with other downloads
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.download = self.download.with_rate_limiter(rate_limiter);
+        self
+    }
     /// Downloads a file. If the file already exists. it will be overwritten
-    pub async fn download(self) -> Result<(), Error> {
+    ///
+    /// Returns the URL that served the file
+    pub async fn download(self) -> Result<Url, Error> {
         self.download_with_subscriber(|_| {}).await
     }
     /// Downloads a file. if a file already exists. it will be overwritten
     /// `subscriber` is a function that is called whenever a new set of bytes is downloaded and written. Param is the number of bytes download
-    pub async fn download_with_subscriber<F>(self, subscriber: F) -> Result<(), Error>
+    ///
+    /// Returns the URL that served the file
+    pub async fn download_with_subscriber<F>(self, subscriber: F) -> Result<Url, Error>
     where
         F: Fn(usize),
     {
-        super::download_with_subscriber(
-            self.download.url,
-            &self.download.client.http_client,
-            self.location,
-            subscriber,
-        )
-        .await
+        self.download
+            .download_with_subscriber(self.location, subscriber)
+            .await
     }
 }
 
@@ -0,0 +1,90 @@
+use std::{io, path::Path};
+
+use sha1::{Digest, Sha1};
+use tokio::{
+    fs::{metadata, File},
+    io::AsyncReadExt,
+};
+
+use crate::{error::HashMismatch, Error};
+
+/// Hex encoded SHA-1 of the bytes
+pub(crate) fn sha1_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha1::digest(bytes))
+}
+
+/// Hex encoded SHA-1 of the file at `path`
+pub(crate) async fn sha1_file(path: impl AsRef<Path>) -> Result<String, Error> {
+    let mut file = File::open(path).await?;
+    let mut hasher = Sha1::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Returns [HashMismatch] if the SHA-1 of `bytes` is not `expected`
+pub(crate) fn verify_sha1(
+    location: impl Into<String>,
+    bytes: &[u8],
+    expected: &str,
+) -> Result<(), HashMismatch> {
+    let actual = sha1_hex(bytes);
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(HashMismatch {
+            location: location.into(),
+            expected: expected.to_owned(),
+            actual,
+        })
+    }
+}
+
+/// The state of a file on disk compared to what was expected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FileState {
+    Missing,
+    Valid,
+    Corrupt {
+        actual_size: u64,
+        /// None if the size already did not match
+        actual_sha1: Option<String>,
+    },
+}
+/// Checks the file exists and matches the size and SHA-1 if given. The file is only hashed if the size matches
+pub(crate) async fn check_file(
+    path: impl AsRef<Path>,
+    size: Option<u64>,
+    sha1: Option<&str>,
+) -> Result<FileState, Error> {
+    let path = path.as_ref();
+    let actual_size = match metadata(path).await {
+        Ok(metadata) => metadata.len(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(FileState::Missing),
+        Err(err) => return Err(err.into()),
+    };
+    if size.is_some_and(|size| size != actual_size) {
+        return Ok(FileState::Corrupt {
+            actual_size,
+            actual_sha1: None,
+        });
+    }
+    let Some(sha1) = sha1 else {
+        return Ok(FileState::Valid);
+    };
+    let actual_sha1 = sha1_file(path).await?;
+    if actual_sha1.eq_ignore_ascii_case(sha1) {
+        Ok(FileState::Valid)
+    } else {
+        Ok(FileState::Corrupt {
+            actual_size,
+            actual_sha1: Some(actual_sha1),
+        })
+    }
+}
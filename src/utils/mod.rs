@@ -1,14 +1,22 @@
-use crate::Error;
+use crate::{http::IntoResult, utils::rate_limit::RateLimiter, Error};
 pub(crate) mod serde_utils;
-use std::path::PathBuf;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
 
-use reqwest::{Client, Url};
+use reqwest::{Client, RequestBuilder, Response, Url};
 use tokio::{
-    fs::{create_dir_all, OpenOptions},
+    fs::{create_dir_all, remove_file, rename, write, OpenOptions},
     io::AsyncWriteExt,
 };
+use tracing::{debug, warn};
 
+pub mod content_store;
 pub mod download;
+pub(crate) mod hash;
+pub mod rate_limit;
 pub async fn download_with_subscriber<F>(
     url: Url,
     reqwest: &Client,
@@ -18,20 +26,164 @@ pub async fn download_with_subscriber<F>(
 where
     F: Fn(usize),
 {
+    download_from_mirrors_with_subscriber(&[url], reqwest, location, subscriber).await?;
+    Ok(())
+}
+/// [download_with_subscriber] throttled by the limiter
+pub async fn download_with_rate_limit<F>(
+    url: Url,
+    reqwest: &Client,
+    location: PathBuf,
+    limiter: &RateLimiter,
+    subscriber: F,
+) -> Result<(), Error>
+where
+    F: Fn(usize),
+{
+    download_from_mirrors_limited(&[url], reqwest, location, &[limiter], subscriber).await?;
+    Ok(())
+}
+/// Downloads from the first URL that can be reached and does not respond with a 5xx.
+///
+/// Returns the URL that served the file
+pub async fn download_from_mirrors_with_subscriber<F>(
+    urls: &[Url],
+    reqwest: &Client,
+    location: PathBuf,
+    subscriber: F,
+) -> Result<Url, Error>
+where
+    F: Fn(usize),
+{
+    download_from_mirrors_limited(urls, reqwest, location, &[], subscriber).await
+}
+/// [download_from_mirrors_with_subscriber] throttled by every limiter given. Such as a global and a per download limit
+pub async fn download_from_mirrors_limited<F>(
+    urls: &[Url],
+    reqwest: &Client,
+    location: PathBuf,
+    limiters: &[&RateLimiter],
+    subscriber: F,
+) -> Result<Url, Error>
+where
+    F: Fn(usize),
+{
+    let (source, url) = send_with_failover(urls, |url| reqwest.get(url)).await?;
+    let mut source = source.into_result::<Error>().await?;
     if let Some(parent) = location.parent() {
         if !parent.exists() {
             create_dir_all(&parent).await?;
         }
     }
+    // Replaced rather than truncated. The file may be a hard link into a content store
+    match remove_file(&location).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
     let mut file = OpenOptions::new()
         .create_new(true)
         .write(true)
         .open(&location)
         .await?;
-    let mut source = reqwest.get(url).send().await?;
-    while let Some(chunk) = source.chunk().await.unwrap() {
+    while let Some(chunk) = source.chunk().await? {
+        for limiter in limiters {
+            limiter.acquire(chunk.len()).await;
+        }
         file.write_all(&chunk).await?;
         subscriber(chunk.len());
     }
-    Ok(())
+    file.flush().await?;
+    Ok(url)
+}
+/// Sends the request to each URL in order until one can be reached and does not respond with a 5xx.
+///
+/// Other errors are returned right away. Returns the response and the URL that served it
+pub(crate) async fn send_with_failover<F>(
+    urls: &[Url],
+    request: F,
+) -> Result<(Response, Url), Error>
+where
+    F: Fn(Url) -> RequestBuilder,
+{
+    let mut last_error = None;
+    for url in urls {
+        match request(url.clone()).send().await.map_err(Error::from) {
+            Ok(response) if response.status().is_server_error() => {
+                warn!(%url, status = %response.status(), "Mirror failed. Trying the next one");
+                last_error = Some(Error::BadResponse(response));
+            }
+            Ok(response) => {
+                debug!(%url, "Served by");
+                return Ok((response, url.clone()));
+            }
+            Err(err) if err.is_connection_error() => {
+                warn!(%url, ?err, "Mirror could not be reached. Trying the next one");
+                last_error = Some(err);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| Error::Custom("No URLs to request".to_owned())))
+}
+static TEMPORARY_ID: AtomicU64 = AtomicU64::new(0);
+/// `{location}.{pid}-{id}.tmp`. Unique per call so concurrent writers never share a temporary file
+pub(crate) fn temporary_path(location: &Path) -> PathBuf {
+    let id = TEMPORARY_ID.fetch_add(1, Ordering::Relaxed);
+    let mut file_name = location.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(format!(".{}-{id}.tmp", std::process::id()));
+    location.with_file_name(file_name)
+}
+/// Writes to a temporary file first so an interrupted write never leaves a partial file behind
+pub(crate) async fn write_atomic(location: &Path, bytes: &[u8]) -> Result<(), Error> {
+    if let Some(parent) = location.parent() {
+        create_dir_all(parent).await?;
+    }
+    let temporary = temporary_path(location);
+    let result = match write(&temporary, bytes).await {
+        Ok(()) => rename(&temporary, location).await,
+        Err(err) => Err(err),
+    };
+    if result.is_err() {
+        let _ = remove_file(&temporary).await;
+    }
+    Ok(result?)
+}
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{temporary_path, write_atomic};
+
+    #[test]
+    fn unique_temporary_paths() {
+        let json = temporary_path(Path::new("versions/1.20.4/1.20.4.json"));
+        let jar = temporary_path(Path::new("versions/1.20.4/1.20.4.jar"));
+        assert_ne!(json, jar);
+        assert!(json
+            .to_string_lossy()
+            .starts_with("versions/1.20.4/1.20.4.json."));
+        assert_ne!(
+            temporary_path(Path::new("1.20.4.json")),
+            temporary_path(Path::new("1.20.4.json"))
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_writes() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let json = dir.path().join("1.20.4.json");
+        let jar = dir.path().join("1.20.4.jar");
+        let (first, second, third) = tokio::join!(
+            write_atomic(&json, b"{}"),
+            write_atomic(&jar, b"jar"),
+            write_atomic(&json, b"{}"),
+        );
+        first?;
+        second?;
+        third?;
+        assert_eq!(std::fs::read(&json)?, b"{}");
+        assert_eq!(std::fs::read(&jar)?, b"jar");
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 2);
+        Ok(())
+    }
 }
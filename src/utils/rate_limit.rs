@@ -0,0 +1,93 @@
+//! Throttles downloads to a number of bytes per second
+use std::{
+    fmt::{Debug, Formatter},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::{sleep, Instant};
+
+/// A token bucket shared by every clone. Allowing one limit across many downloads
+///
+/// Set globally with [APIClient::with_rate_limiter](crate::APIClient::with_rate_limiter) or per download with [Download::with_rate_limiter](super::download::Download::with_rate_limiter)
+#[derive(Clone)]
+pub struct RateLimiter(Arc<Mutex<Bucket>>);
+struct Bucket {
+    bytes_per_second: u64,
+    /// Negative when downloads have used more than was available
+    available: f64,
+    updated: Instant,
+}
+impl RateLimiter {
+    /// Allows bursts of up to one second of bytes
+    pub fn new(bytes_per_second: u64) -> Self {
+        let bytes_per_second = bytes_per_second.max(1);
+        Self(Arc::new(Mutex::new(Bucket {
+            bytes_per_second,
+            available: bytes_per_second as f64,
+            updated: Instant::now(),
+        })))
+    }
+    pub fn bytes_per_second(&self) -> u64 {
+        self.0.lock().unwrap().bytes_per_second
+    }
+    /// Changes the limit of every download using the limiter
+    pub fn set_bytes_per_second(&self, bytes_per_second: u64) {
+        let mut bucket = self.0.lock().unwrap();
+        bucket.refill();
+        bucket.bytes_per_second = bytes_per_second.max(1);
+    }
+    /// Takes the bytes from the bucket. Waiting until the bucket is no longer in debt
+    pub async fn acquire(&self, bytes: usize) {
+        let wait = {
+            let mut bucket = self.0.lock().unwrap();
+            bucket.refill();
+            bucket.available -= bytes as f64;
+            if bucket.available < 0.0 {
+                Duration::from_secs_f64(-bucket.available / bucket.bytes_per_second as f64)
+            } else {
+                Duration::ZERO
+            }
+        };
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+    }
+}
+impl Bucket {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.updated = now;
+        self.available = (self.available + elapsed * self.bytes_per_second as f64)
+            .min(self.bytes_per_second as f64);
+    }
+}
+impl Debug for RateLimiter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RateLimiter({} bytes/s)", self.bytes_per_second())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use crate::utils::rate_limit::RateLimiter;
+
+    #[tokio::test(start_paused = true)]
+    async fn acquire() {
+        let limiter = RateLimiter::new(1000);
+        let start = Instant::now();
+        limiter.acquire(1000).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        limiter.acquire(500).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+
+        let shared = limiter.clone();
+        shared.set_bytes_per_second(2000);
+        limiter.acquire(1000).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(1000));
+    }
+}